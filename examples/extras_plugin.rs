@@ -1,9 +1,10 @@
 //! BRP extras test example with keyboard input display
 //!
-//! This example demonstrates bevy_brp_extras functionality including:
+//! This example demonstrates `bevy_brp_extras` functionality including:
 //! - Format discovery
 //! - Screenshot capture
 //! - Keyboard input simulation
+//! - UI clicks through `brp_extras/click_ui`
 //! - Debug mode toggling
 //!
 //! Used by the test suite to validate all extras functionality.
@@ -35,6 +36,10 @@ struct KeyboardInputHistory {
 #[derive(Component)]
 struct KeyboardDisplayText;
 
+/// Number of times the test button has been pressed
+#[derive(Component, Default)]
+struct ClickCount(u32);
+
 fn main() {
     let brp_plugin = BrpExtrasPlugin::new();
     let (port, _) = brp_plugin.get_effective_port();
@@ -54,7 +59,14 @@ fn main() {
         .init_resource::<KeyboardInputHistory>()
         .insert_resource(CurrentPort(port))
         .add_systems(Startup, (setup_test_entities, setup_ui))
-        .add_systems(Update, (track_keyboard_input, update_keyboard_display))
+        .add_systems(
+            Update,
+            (
+                track_keyboard_input,
+                update_keyboard_display,
+                count_button_clicks,
+            ),
+        )
         .run();
 }
 
@@ -63,6 +75,7 @@ fn main() {
 struct CurrentPort(u16);
 
 /// Setup test entities for format discovery
+#[allow(clippy::needless_pass_by_value)]
 fn setup_test_entities(mut commands: Commands, port: Res<CurrentPort>) {
     info!("Setting up test entities...");
 
@@ -99,6 +112,7 @@ fn setup_test_entities(mut commands: Commands, port: Res<CurrentPort>) {
 }
 
 /// Setup UI for keyboard input display
+#[allow(clippy::needless_pass_by_value)]
 fn setup_ui(mut commands: Commands, port: Res<CurrentPort>) {
     // Camera
     commands.spawn(Camera2d);
@@ -109,13 +123,39 @@ fn setup_ui(mut commands: Commands, port: Res<CurrentPort>) {
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.0),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..default()
             },
             BackgroundColor(Color::srgb(0.1, 0.1, 0.1)),
+            Name::new("Background"),
         ))
         .with_children(|parent| {
+            // Button for brp_extras/click_ui, found by the name path "Background/ClickButton"
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.3, 0.5)),
+                    Name::new("ClickButton"),
+                    ClickCount::default(),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Clicks: 0"),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+
             // Text container
             parent
                 .spawn((
@@ -143,6 +183,25 @@ fn setup_ui(mut commands: Commands, port: Res<CurrentPort>) {
         });
 }
 
+/// Count presses of the test button and show the total in its label
+fn count_button_clicks(
+    mut buttons: Query<(&Interaction, &mut ClickCount, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (interaction, mut count, children) in &mut buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        count.0 += 1;
+        info!("Button clicked {} times", count.0);
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = format!("Clicks: {}", count.0);
+            }
+        }
+    }
+}
+
 /// Track keyboard input events
 fn track_keyboard_input(
     mut events: EventReader<KeyboardInput>,
@@ -199,12 +258,14 @@ fn track_keyboard_input(
         }
 
         if !history.active_keys.is_empty() {
-            history.last_keys = history.active_keys.clone();
+            let history = &mut *history;
+            history.last_keys.clone_from(&history.active_keys);
         }
     }
 }
 
 /// Update the keyboard display
+#[allow(clippy::needless_pass_by_value)]
 fn update_keyboard_display(
     history: Res<KeyboardInputHistory>,
     mut query: Query<&mut Text, With<KeyboardDisplayText>>,
@@ -0,0 +1,421 @@
+//! Screenshot comparison against baseline images
+//!
+//! Captures a screenshot the same way `brp_extras/screenshot` does and compares it pixel by pixel
+//! with a baseline image on disk, for visual regression tests driven over BRP.
+
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::render::view::screenshot::Screenshot;
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::deferred::{DeferredStep, run_deferred, wait_deadline};
+use crate::output::{check_overwrite, overwrite_allowed, resolve_output_path, write_file};
+use crate::screenshot::{CaptureTarget, crop, resolve_crop, resolve_target, spawn_capture};
+
+/// Color of differing pixels in the diff image
+const DIFF_HIGHLIGHT: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Request parameters for screenshot comparisons
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompareScreenshotRequest {
+    /// Path of the baseline image to compare against
+    pub baseline:       String,
+    /// Largest per-channel difference, from 0 to 255, at which pixels still count as equal
+    #[serde(default)]
+    pub tolerance:      u8,
+    /// Largest fraction of differing pixels, from 0 to 1, at which the comparison passes
+    #[serde(default)]
+    pub max_diff_ratio: f64,
+    /// Path to save an image highlighting the differing pixels to
+    #[serde(default)]
+    pub diff_path:      Option<String>,
+    /// Replace an existing file at `diff_path`, defaults to true without an output root and false
+    /// with one
+    #[serde(default)]
+    pub overwrite:      Option<bool>,
+    /// What to capture and which region of it to compare
+    #[serde(flatten)]
+    pub target:         CaptureTarget,
+    /// Maximum time to wait for the capture in milliseconds
+    #[serde(default)]
+    pub timeout_ms:     Option<u32>,
+}
+
+/// Response for screenshot comparisons
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareScreenshotResponse {
+    /// Whether the comparison ran
+    pub success:          bool,
+    /// Whether the fraction of differing pixels is within `max_diff_ratio`
+    pub passed:           bool,
+    /// Absolute path of the baseline image
+    pub baseline:         String,
+    /// Compared width in pixels
+    pub width:            u32,
+    /// Compared height in pixels
+    pub height:           u32,
+    /// Number of pixels that differ by more than the tolerance
+    pub differing_pixels: u64,
+    /// Number of pixels compared
+    pub total_pixels:     u64,
+    /// Fraction of pixels that differ by more than the tolerance
+    pub diff_ratio:       f64,
+    /// Mean absolute difference over all channels, from 0 (identical) to 1
+    pub diff_score:       f64,
+    /// Absolute path the diff image was saved to, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_path:        Option<String>,
+}
+
+/// Slot the comparison task writes its result into
+type ComparisonSlot = Arc<Mutex<Option<Result<CompareScreenshotResponse, String>>>>;
+
+/// State of a comparison waiting for its capture
+pub struct PendingComparison {
+    slot:     ComparisonSlot,
+    deadline: Instant,
+}
+
+/// Result of comparing two images of the same size
+struct Comparison {
+    differing_pixels: u64,
+    total_pixels:     u64,
+    diff_score:       f64,
+    diff_image:       RgbaImage,
+}
+
+/// Handler for screenshot comparison requests
+///
+/// Captures a screenshot, compares it with the baseline image and responds with the result
+/// once the capture has been processed. Every request captures a new frame, including repeats
+/// of an earlier comparison with the same params.
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult<Option<Value>> {
+    run_deferred(
+        world,
+        params,
+        |world, params| match start_comparison(world, params) {
+            Ok(step) => step,
+            Err(e) => DeferredStep::Ready(Err(e)),
+        },
+        poll_comparison,
+    )
+}
+
+fn start_comparison(
+    world: &mut World,
+    params: Option<Value>,
+) -> Result<DeferredStep<PendingComparison>, BrpError> {
+    let request: CompareScreenshotRequest = params
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Missing 'baseline' parameter".to_string(),
+            data:    None,
+        })
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|e| BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!("Invalid comparison parameters: {e}"),
+                data:    None,
+            })
+        })?;
+
+    if !(0.0..=1.0).contains(&request.max_diff_ratio) {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "'max_diff_ratio' must be between 0 and 1".to_string(),
+            data:    None,
+        });
+    }
+
+    let deadline = wait_deadline(request.timeout_ms)?;
+    let baseline = resolve_output_path(world, &request.baseline)?;
+    if !baseline.is_file() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Baseline image not found: {}", baseline.display()),
+            data:    None,
+        });
+    }
+    let baseline = baseline.to_string_lossy().to_string();
+    let diff_path = request
+        .diff_path
+        .as_deref()
+        .map(|path| resolve_output_path(world, path))
+        .transpose()?;
+    let overwrite = overwrite_allowed(world, request.overwrite);
+    if let Some(path) = &diff_path {
+        check_overwrite(path, overwrite)?;
+    }
+    let diff_path = diff_path.map(|path| path.to_string_lossy().to_string());
+
+    let (target, target_description) = resolve_target(world, &request.target)?;
+    let crop_rect = resolve_crop(world, &request.target)?;
+
+    info!("Comparing screenshot of {target_description} with baseline {baseline}");
+
+    let slot = ComparisonSlot::default();
+    let task_slot = slot.clone();
+    let tolerance = request.tolerance;
+    let max_diff_ratio = request.max_diff_ratio;
+    spawn_capture(
+        world,
+        Screenshot(target),
+        format!("Screenshot_compare_{baseline}"),
+        move |img| {
+            let result = img
+                .try_into_dynamic()
+                .map_err(|e| format!("Failed to convert screenshot to dynamic image: {e}"))
+                .and_then(|dyn_img| crop(dyn_img, crop_rect))
+                .and_then(|captured| {
+                    let baseline_image = image::open(&baseline)
+                        .map_err(|e| format!("Failed to load baseline image {baseline}: {e}"))?;
+                    let comparison = compare_images(
+                        &captured.to_rgba8(),
+                        &baseline_image.to_rgba8(),
+                        tolerance,
+                    )?;
+
+                    if let Some(path) = &diff_path {
+                        save_diff_image(&comparison.diff_image, path, overwrite)?;
+                    }
+
+                    Ok(comparison_response(
+                        &comparison,
+                        captured.width(),
+                        captured.height(),
+                        baseline.clone(),
+                        diff_path.clone(),
+                        max_diff_ratio,
+                    ))
+                });
+
+            if let Err(e) = &result {
+                error!("{e}");
+            }
+            if let Ok(mut guard) = task_slot.lock() {
+                *guard = Some(result);
+            }
+        },
+    );
+
+    Ok(DeferredStep::Pending(PendingComparison { slot, deadline }))
+}
+
+fn poll_comparison(
+    _world: &mut World,
+    pending: PendingComparison,
+) -> DeferredStep<PendingComparison> {
+    let result = pending.slot.lock().ok().and_then(|mut guard| guard.take());
+
+    match result {
+        Some(Ok(response)) => {
+            DeferredStep::Ready(serde_json::to_value(response).map_err(|e| BrpError {
+                code:    error_codes::INTERNAL_ERROR,
+                message: format!("Failed to serialize comparison response: {e}"),
+                data:    None,
+            }))
+        }
+        Some(Err(message)) => DeferredStep::Ready(Err(BrpError {
+            code: error_codes::INTERNAL_ERROR,
+            message,
+            data: None,
+        })),
+        None if Instant::now() >= pending.deadline => DeferredStep::Ready(Err(BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: "Timed out waiting for the screenshot to compare. Screenshots require a \
+                      running renderer and a visible window, or headless mode enabled with \
+                      BrpExtrasPlugin::with_headless"
+                .to_string(),
+            data:    None,
+        })),
+        None => DeferredStep::Pending(pending),
+    }
+}
+
+// Pixel counts stay far below 2^52, so the conversions to f64 are exact
+#[allow(clippy::cast_precision_loss)]
+fn comparison_response(
+    comparison: &Comparison,
+    width: u32,
+    height: u32,
+    baseline: String,
+    diff_path: Option<String>,
+    max_diff_ratio: f64,
+) -> CompareScreenshotResponse {
+    let diff_ratio = if comparison.total_pixels == 0 {
+        0.0
+    } else {
+        comparison.differing_pixels as f64 / comparison.total_pixels as f64
+    };
+
+    CompareScreenshotResponse {
+        success: true,
+        passed: diff_ratio <= max_diff_ratio,
+        baseline,
+        width,
+        height,
+        differing_pixels: comparison.differing_pixels,
+        total_pixels: comparison.total_pixels,
+        diff_ratio,
+        diff_score: comparison.diff_score,
+        diff_path,
+    }
+}
+
+/// Compare two images pixel by pixel
+///
+/// Pixels count as differing when any channel differs by more than `tolerance`. The diff image
+/// shows the baseline dimmed to grayscale with differing pixels highlighted.
+#[allow(clippy::cast_precision_loss)]
+fn compare_images(
+    captured: &RgbaImage,
+    baseline: &RgbaImage,
+    tolerance: u8,
+) -> Result<Comparison, String> {
+    if captured.dimensions() != baseline.dimensions() {
+        return Err(format!(
+            "Screenshot is {}x{} but the baseline is {}x{}",
+            captured.width(),
+            captured.height(),
+            baseline.width(),
+            baseline.height()
+        ));
+    }
+
+    let mut diff_image = RgbaImage::new(baseline.width(), baseline.height());
+    let mut differing_pixels = 0_u64;
+    let mut channel_difference = 0_u64;
+
+    for ((captured, expected), diff) in captured
+        .pixels()
+        .zip(baseline.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let differences = [0, 1, 2, 3].map(|channel| captured[channel].abs_diff(expected[channel]));
+        channel_difference += differences.iter().map(|&d| u64::from(d)).sum::<u64>();
+
+        if differences.iter().any(|&d| d > tolerance) {
+            differing_pixels += 1;
+            *diff = DIFF_HIGHLIGHT;
+        } else {
+            let luminance = (u16::from(expected[0]) * 77
+                + u16::from(expected[1]) * 150
+                + u16::from(expected[2]) * 29)
+                >> 8;
+            let dimmed = u8::try_from(luminance / 3).unwrap_or(u8::MAX);
+            *diff = Rgba([dimmed, dimmed, dimmed, 255]);
+        }
+    }
+
+    let total_pixels = u64::from(baseline.width()) * u64::from(baseline.height());
+    let diff_score = if total_pixels == 0 {
+        0.0
+    } else {
+        channel_difference as f64 / (total_pixels * 4 * 255) as f64
+    };
+
+    Ok(Comparison {
+        differing_pixels,
+        total_pixels,
+        diff_score,
+        diff_image,
+    })
+}
+
+/// Save the diff image, creating parent directories as needed
+fn save_diff_image(diff_image: &RgbaImage, path: &str, overwrite: bool) -> Result<(), String> {
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory for diff image {path}: {e}"))?;
+    }
+    let format = ImageFormat::from_path(path)
+        .map_err(|e| format!("Failed to save diff image to {path}: {e}"))?;
+    let mut bytes = Cursor::new(Vec::new());
+    diff_image
+        .write_to(&mut bytes, format)
+        .map_err(|e| format!("Failed to save diff image to {path}: {e}"))?;
+    write_file(Path::new(path), bytes.get_ref(), overwrite)
+        .map_err(|e| format!("Failed to save diff image to {path}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::deferred::with_request_id;
+    use crate::output::{OutputRoot, unique_temp_dir};
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_compare_images_with_tolerance() {
+        let baseline = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut captured = baseline.clone();
+        captured.put_pixel(0, 0, Rgba([104, 100, 100, 255]));
+        captured.put_pixel(1, 0, Rgba([200, 100, 100, 255]));
+
+        let strict = compare_images(&captured, &baseline, 0).expect("Expected comparison");
+        assert_eq!(strict.differing_pixels, 2);
+        assert_eq!(strict.total_pixels, 16);
+        assert_eq!(*strict.diff_image.get_pixel(1, 0), DIFF_HIGHLIGHT);
+        assert!(strict.diff_score > 0.0);
+
+        let tolerant = compare_images(&captured, &baseline, 5).expect("Expected comparison");
+        assert_eq!(tolerant.differing_pixels, 1);
+
+        let response = comparison_response(&tolerant, 4, 4, String::new(), None, 0.1);
+        assert!(response.passed);
+        let response = comparison_response(&strict, 4, 4, String::new(), None, 0.1);
+        assert!(!response.passed);
+
+        let other_size = RgbaImage::new(2, 2);
+        assert!(compare_images(&other_size, &baseline, 0).is_err());
+    }
+
+    #[test]
+    fn test_missing_baseline_rejected() {
+        let mut world = World::new();
+        let result = handler(
+            In(with_request_id(
+                json!({ "baseline": "does/not/exist.png" }),
+                1,
+            )),
+            &mut world,
+        );
+        assert!(result.is_err_and(|e| e.message.contains("Baseline image not found")));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_baseline_resolved_in_output_root() {
+        let root = unique_temp_dir("compare_root");
+        let mut world = World::new();
+        world.insert_resource(OutputRoot(Some(root.clone())));
+
+        let error = handler(
+            In(with_request_id(json!({ "baseline": "missing.png" }), 1)),
+            &mut world,
+        )
+        .expect_err("Expected a missing baseline");
+        assert!(
+            error
+                .message
+                .contains(&*root.join("missing.png").to_string_lossy())
+        );
+
+        let error = handler(
+            In(with_request_id(json!({ "baseline": "../escape.png" }), 2)),
+            &mut world,
+        )
+        .expect_err("Expected a baseline outside the root to be rejected");
+        assert_eq!(error.code, error_codes::INVALID_PARAMS);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
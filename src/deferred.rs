@@ -0,0 +1,454 @@
+//! Support for BRP methods that respond after one or more frames
+//!
+//! Bevy runs a watching method handler every frame until it returns a value. When the method is
+//! registered under a name without `+watch`, the HTTP transport waits for that first value and
+//! sends it as an ordinary response, which lets a method reply once work spanning several frames
+//! has finished.
+//!
+//! The handler is re-run with the same params every frame and is never told which request it is
+//! running for. The [`DeferredMailbox`] therefore takes over the receiving end of the BRP channel
+//! and adds a unique request ID to the params of every deferred method before Bevy sees the
+//! request, replacing any ID a client sent. The state of each in-flight request is stored in a
+//! [`DeferredRequests`] resource keyed by that ID, so repeated or concurrent requests with the
+//! same params each do their own work. Requests without an ID are rejected rather than keyed by
+//! their params. The channel the mailbox forwards to is bounded like Bevy's own, so transports
+//! still wait when requests arrive faster than they are processed.
+//!
+//! Bevy can also run the handler once more after it responded, before the finished request is
+//! cleaned up. Finished results are therefore kept until the request stops polling so the extra
+//! run returns the same result instead of starting the work over.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_channel::{Receiver, Sender, TrySendError};
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpMessage, BrpReceiver, BrpResult, error_codes};
+use serde_json::{Value, json};
+
+/// Number of frames an entry can go without being polled before it is removed
+const MAX_IDLE_FRAMES: u8 = 2;
+
+/// Default time a deferred request waits in milliseconds
+const DEFAULT_WAIT_TIMEOUT_MS: u32 = 5_000;
+
+/// Maximum time a deferred request can wait in milliseconds
+const MAX_WAIT_TIMEOUT_MS: u32 = 60_000;
+
+/// Params field the [`DeferredMailbox`] stores each request's ID in
+const REQUEST_ID_KEY: &str = "brp_extras_request_id";
+
+/// Outcome of starting or polling a deferred request
+pub enum DeferredStep<S> {
+    /// The request is still in progress with the given state
+    Pending(S),
+    /// The request has finished with the given result
+    Ready(BrpResult),
+}
+
+/// State of a single deferred request
+enum DeferredState<S> {
+    Pending(S),
+    Finished(BrpResult),
+}
+
+/// Entry tracking a deferred request and how long it has gone without being polled
+struct DeferredEntry<S> {
+    state:       DeferredState<S>,
+    idle_frames: u8,
+}
+
+/// Resource holding the in-flight and recently finished requests of one deferred method
+#[derive(Resource)]
+pub struct DeferredRequests<S: Send + Sync + 'static> {
+    entries: HashMap<u64, DeferredEntry<S>>,
+}
+
+impl<S: Send + Sync + 'static> Default for DeferredRequests<S> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+/// Drive a deferred request from a watching handler
+///
+/// `start` runs the first time a request is seen and receives its params without the request ID,
+/// `poll` runs on every following frame until either returns [`DeferredStep::Ready`].
+///
+/// # Errors
+///
+/// Returns `BrpError` if the request didn't get an ID from the [`DeferredMailbox`]
+pub fn run_deferred<S: Send + Sync + 'static>(
+    world: &mut World,
+    params: Option<Value>,
+    start: impl FnOnce(&mut World, Option<Value>) -> DeferredStep<S>,
+    poll: impl FnOnce(&mut World, S) -> DeferredStep<S>,
+) -> BrpResult<Option<Value>> {
+    let (key, params) = request_key(params)?;
+    let existing = world
+        .get_resource_or_init::<DeferredRequests<S>>()
+        .entries
+        .remove(&key);
+
+    let step = match existing.map(|entry| entry.state) {
+        Some(DeferredState::Finished(result)) => DeferredStep::Ready(result),
+        Some(DeferredState::Pending(state)) => poll(world, state),
+        None => start(world, params),
+    };
+
+    let (state, response) = match step {
+        DeferredStep::Pending(state) => (DeferredState::Pending(state), Ok(None)),
+        DeferredStep::Ready(result) => {
+            let response = result.clone().map(Some);
+            (DeferredState::Finished(result), response)
+        }
+    };
+
+    world
+        .get_resource_or_init::<DeferredRequests<S>>()
+        .entries
+        .insert(
+            key,
+            DeferredEntry {
+                state,
+                idle_frames: 0,
+            },
+        );
+
+    response
+}
+
+/// Deadline for a deferred request that waits, validating the requested timeout
+pub fn wait_deadline(timeout_ms: Option<u32>) -> Result<Instant, BrpError> {
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS);
+    if timeout_ms > MAX_WAIT_TIMEOUT_MS {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!(
+                "Timeout {timeout_ms}ms exceeds maximum allowed timeout of \
+                 {MAX_WAIT_TIMEOUT_MS}ms"
+            ),
+            data:    None,
+        });
+    }
+    Ok(Instant::now() + Duration::from_millis(u64::from(timeout_ms)))
+}
+
+/// Split the request ID from a request's params
+///
+/// The mailbox adds an object to requests sent without params, so an object that only held the
+/// ID is passed on as no params.
+fn request_key(mut params: Option<Value>) -> Result<(u64, Option<Value>), BrpError> {
+    let Some(Value::Object(map)) = &mut params else {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Parameters must be an object".to_string(),
+            data:    None,
+        });
+    };
+    let id = map
+        .remove(REQUEST_ID_KEY)
+        .and_then(|id| id.as_u64())
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: "Request has no deferred request ID. BrpExtrasPlugin must be added to the \
+                      app for this method to run"
+                .to_string(),
+            data:    None,
+        })?;
+    if map.is_empty() {
+        params = None;
+    }
+    Ok((id, params))
+}
+
+/// Add a request ID to a request's params
+///
+/// Params that aren't an object are left alone, the handler rejects them when it starts.
+pub fn add_request_id(params: &mut Option<Value>, id: u64) {
+    match params {
+        Some(Value::Object(map)) => {
+            map.insert(REQUEST_ID_KEY.to_string(), json!(id));
+        }
+        None => *params = Some(json!({ REQUEST_ID_KEY: id })),
+        Some(_) => {}
+    }
+}
+
+/// Params for a test request as the mailbox would forward them
+#[cfg(test)]
+pub fn with_request_id(params: Value, id: u64) -> Option<Value> {
+    let mut params = Some(params);
+    add_request_id(&mut params, id);
+    params
+}
+
+/// Resource that gives every request for a deferred method its own ID
+///
+/// It holds the original receiving end of the BRP channel and forwards each message to the
+/// channel Bevy's `process_remote_requests` now reads from.
+#[derive(Resource)]
+pub struct DeferredMailbox {
+    methods: Vec<String>,
+    channel: Option<(Receiver<BrpMessage>, Sender<BrpMessage>)>,
+    /// A message that didn't fit into the forwarding channel, sent before any newer one
+    held:    Option<BrpMessage>,
+    next_id: u64,
+}
+
+impl DeferredMailbox {
+    /// Create a mailbox that assigns IDs to requests for the given methods
+    pub fn new(methods: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            methods: methods.into_iter().collect(),
+            channel: None,
+            held:    None,
+            next_id: 0,
+        }
+    }
+}
+
+/// System that puts the mailbox between the BRP transports and Bevy's request processing
+///
+/// Runs after `RemotePlugin` created the channel in `PreStartup`. Transports keep sending to the
+/// original channel, while Bevy reads from a new one of the same capacity the mailbox forwards to.
+pub fn setup_mailbox(mut mailbox: ResMut<DeferredMailbox>, receiver: Option<ResMut<BrpReceiver>>) {
+    let Some(mut receiver) = receiver else {
+        error!(
+            "BRP receiver is missing, requests for {:?} will fail",
+            mailbox.methods
+        );
+        return;
+    };
+    let (forward, forwarded) = async_channel::bounded(receiver.capacity().unwrap_or(1));
+    let incoming = std::mem::replace(&mut **receiver, forwarded);
+    mailbox.channel = Some((incoming, forward));
+}
+
+/// System that forwards BRP requests to Bevy, adding request IDs for deferred methods
+///
+/// Runs in `RemoteLast` before `RemoteSet::ProcessRequests`. Once the forwarding channel is full
+/// the remaining requests stay in the original channel until the next frame.
+pub fn forward_requests(mut mailbox: ResMut<DeferredMailbox>) {
+    let DeferredMailbox {
+        methods,
+        channel: Some((incoming, forward)),
+        held,
+        next_id,
+    } = &mut *mailbox
+    else {
+        return;
+    };
+
+    loop {
+        let message = match held.take() {
+            Some(message) => message,
+            None => match incoming.try_recv() {
+                Ok(mut message) => {
+                    if methods.contains(&message.method) {
+                        *next_id += 1;
+                        add_request_id(&mut message.params, *next_id);
+                    }
+                    message
+                }
+                Err(_) => return,
+            },
+        };
+
+        match forward.try_send(message) {
+            Ok(()) => {}
+            Err(TrySendError::Full(message)) => {
+                *held = Some(message);
+                return;
+            }
+            Err(TrySendError::Closed(message)) => {
+                let error = BrpError {
+                    code:    error_codes::INTERNAL_ERROR,
+                    message: "BRP requests are no longer being processed".to_string(),
+                    data:    None,
+                };
+                if message.sender.force_send(Err(error)).is_err() {
+                    warn!("Dropped BRP request for {}", message.method);
+                }
+            }
+        }
+    }
+}
+
+/// System that removes requests which are no longer being polled
+///
+/// Requests stop being polled once their response was delivered or the client disconnected.
+pub fn expire_idle_requests<S: Send + Sync + 'static>(
+    requests: Option<ResMut<DeferredRequests<S>>>,
+) {
+    let Some(mut requests) = requests else {
+        return;
+    };
+
+    requests.entries.retain(|_, entry| {
+        entry.idle_frames = entry.idle_frames.saturating_add(1);
+        entry.idle_frames < MAX_IDLE_FRAMES
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::remote::{BrpSender, RemoteLast, RemotePlugin, RemoteSet};
+
+    use super::*;
+
+    /// Number of captures started by [`capture_handler`]
+    #[derive(Resource, Default)]
+    struct Captures(u32);
+
+    /// Deferred handler that starts a capture and responds with its number one frame later
+    fn capture_handler(
+        In(params): In<Option<Value>>,
+        world: &mut World,
+    ) -> BrpResult<Option<Value>> {
+        run_deferred::<u32>(
+            world,
+            params,
+            |world, _| {
+                let mut captures = world.get_resource_or_init::<Captures>();
+                captures.0 += 1;
+                DeferredStep::Pending(captures.0)
+            },
+            |_, capture| DeferredStep::Ready(Ok(json!(capture))),
+        )
+    }
+
+    fn poll_counter(world: &mut World, params: &Value) -> BrpResult<Option<Value>> {
+        run_deferred::<u32>(
+            world,
+            Some(params.clone()),
+            |_, _| DeferredStep::Pending(0),
+            |_, polls| {
+                if polls >= 2 {
+                    DeferredStep::Ready(Ok(json!(polls)))
+                } else {
+                    DeferredStep::Pending(polls + 1)
+                }
+            },
+        )
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_deferred_request_finishes_and_repeats_result() {
+        let mut world = World::new();
+        let params = json!({ "id": 1, REQUEST_ID_KEY: 1 });
+
+        assert!(matches!(poll_counter(&mut world, &params), Ok(None)));
+        assert!(matches!(poll_counter(&mut world, &params), Ok(None)));
+        assert!(matches!(poll_counter(&mut world, &params), Ok(None)));
+        let result = poll_counter(&mut world, &params).expect("Expected success");
+        assert_eq!(result, Some(json!(2)));
+
+        // A re-run after finishing returns the same result rather than starting over
+        let result = poll_counter(&mut world, &params).expect("Expected success");
+        assert_eq!(result, Some(json!(2)));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_request_id_is_removed_from_params() {
+        // An ID sent by the client is replaced by the one the mailbox assigns
+        let mut params = Some(json!({ "path": "x.png", REQUEST_ID_KEY: 1 }));
+        add_request_id(&mut params, 7);
+        let (id, params) = request_key(params).expect("Expected a request ID");
+        assert_eq!(id, 7);
+        assert_eq!(params, Some(json!({ "path": "x.png" })));
+
+        let mut params = None;
+        add_request_id(&mut params, 8);
+        let (id, params) = request_key(params).expect("Expected a request ID");
+        assert_eq!(id, 8);
+        assert_eq!(params, None);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_requests_without_id_are_rejected() {
+        let mut world = World::new();
+
+        let error = poll_counter(&mut world, &json!({ "id": 3 })).expect_err("Expected an error");
+        assert_eq!(error.code, error_codes::INTERNAL_ERROR);
+        let error = poll_counter(&mut world, &json!([3])).expect_err("Expected an error");
+        assert_eq!(error.code, error_codes::INVALID_PARAMS);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_requests_with_same_params_capture_separately() {
+        const METHOD: &str = "test/capture";
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            RemotePlugin::default().with_watching_method(METHOD, capture_handler),
+        ))
+        .insert_resource(DeferredMailbox::new([METHOD.to_string()]))
+        .add_systems(Startup, setup_mailbox)
+        .add_systems(
+            RemoteLast,
+            forward_requests.before(RemoteSet::ProcessRequests),
+        );
+        app.update();
+
+        let send = |app: &App| {
+            let (sender, receiver) = async_channel::bounded(1);
+            app.world()
+                .resource::<BrpSender>()
+                .try_send(BrpMessage {
+                    method: METHOD.to_string(),
+                    params: Some(json!({ "path": "x.png", "overwrite": true })),
+                    sender,
+                })
+                .expect("Expected the request to be sent");
+            receiver
+        };
+
+        // A repeat sent while the first request still gets its extra run captures again
+        let first = send(&app);
+        app.update();
+        app.update();
+        let first_result = first.try_recv().expect("Expected a response");
+        let second = send(&app);
+        app.update();
+        app.update();
+        assert_eq!(first_result.expect("Expected success"), json!(1));
+        let second_result = second.try_recv().expect("Expected a response");
+        assert_eq!(second_result.expect("Expected success"), json!(2));
+
+        // Identical requests in flight at the same time don't share a capture
+        let third = send(&app);
+        let fourth = send(&app);
+        app.update();
+        app.update();
+        let mut results = [third, fourth].map(|receiver| {
+            receiver
+                .try_recv()
+                .expect("Expected a response")
+                .expect("Expected success")
+        });
+        results.sort_by_key(Value::as_u64);
+        assert_eq!(results, [json!(3), json!(4)]);
+        assert_eq!(app.world().resource::<Captures>().0, 4);
+    }
+
+    #[test]
+    fn test_idle_requests_expire() {
+        let mut world = World::new();
+        let params = json!({ "id": 2, REQUEST_ID_KEY: 2 });
+        let _ = poll_counter(&mut world, &params);
+
+        for _ in 0..MAX_IDLE_FRAMES {
+            let _ = world.run_system_once(expire_idle_requests::<u32>);
+        }
+
+        assert_eq!(world.resource::<DeferredRequests<u32>>().entries.len(), 0);
+    }
+}
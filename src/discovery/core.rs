@@ -10,10 +10,10 @@ use serde_json::{Value, json};
 
 use super::error::{DebugContext, DiscoveryResult};
 use super::mutation::generate_mutation_info;
-use super::registry::get_type_info_from_registry;
+use super::registry::{event_buffered, get_type_info_from_registry};
 use super::spawn::generate_spawn_format;
 use super::types::is_mutable_type;
-use crate::format::FormatInfo;
+use crate::format::{EventInfo, FormatInfo};
 
 /// Result of discovering multiple component formats
 #[derive(Debug, Clone)]
@@ -49,16 +49,40 @@ pub fn discover_component_format(
         }
     };
 
+    // Events are sent with the same payload shape used to spawn them as components
+    let event_format = event_buffered(world, type_name, debug_context.as_mut_vec())?
+        .map(|buffered| event_info(type_name, buffered, spawn_info.example.clone()));
+
     let format_info = FormatInfo {
         type_name: type_name.to_string(),
         spawn_format: spawn_info,
         mutation_info,
+        event_format,
     };
 
     debug_context.push("Successfully generated format info".to_string());
     Ok(format_info)
 }
 
+/// Describe how to send an event type, or why it can't be sent over BRP
+fn event_info(type_name: &str, buffered: bool, example: Value) -> EventInfo {
+    let description = if buffered {
+        format!("Send {type_name} with brp_extras/send_event, passing this example as 'value'")
+    } else {
+        format!(
+            "{type_name} has #[reflect(Event)] but Events<{type_name}> doesn't exist, add the \
+             event with App::add_event before sending it with brp_extras/send_event"
+        )
+    };
+
+    EventInfo {
+        method: "brp_extras/send_event".to_string(),
+        example,
+        buffered,
+        description,
+    }
+}
+
 /// Discover format information for multiple component types
 pub fn discover_multiple_formats(world: &World, type_names: &[String]) -> MultiDiscoveryResult {
     let mut debug_context = DebugContext::new();
@@ -139,10 +163,10 @@ pub fn create_discovery_response(
     }
 
     // Add debug info if provided
-    if let Some(debug_ctx) = debug_context {
-        if !debug_ctx.messages.is_empty() {
-            response["debug_info"] = json!(debug_ctx.messages);
-        }
+    if let Some(debug_ctx) = debug_context
+        && !debug_ctx.messages.is_empty()
+    {
+        response["debug_info"] = json!(debug_ctx.messages);
     }
 
     // Add summary information
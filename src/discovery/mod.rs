@@ -29,17 +29,19 @@ pub use handler::{
     discover_multiple_formats_public as discover_multiple_formats,
     get_common_component_types_public as get_common_component_types, handler,
 };
-
 // Re-export core discovery functions for advanced users
 // (None currently needed publicly)
 
 // Internal-only re-exports for the modules to use each other
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) use registry::find_registration;
 
 #[cfg(test)]
 mod tests {
     use bevy::prelude::*;
 
     use super::*;
+    use crate::ReflectEvent;
     use crate::discovery::error::{DebugContext, DiscoveryError};
 
     #[test]
@@ -49,6 +51,44 @@ mod tests {
         assert!(types.contains(&"bevy_transform::components::transform::Transform".to_string()));
     }
 
+    #[derive(Event, Reflect)]
+    #[reflect(Event)]
+    struct Jump {
+        height: f32,
+    }
+
+    #[derive(Event, Reflect)]
+    struct Land {
+        speed: f32,
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_event_format() {
+        let mut app = App::new();
+        app.add_event::<Jump>()
+            .add_event::<Land>()
+            .register_type::<Jump>()
+            .register_type::<Land>()
+            .register_type::<Transform>();
+
+        let format = discover_component_format(app.world(), Jump::type_path())
+            .expect("Expected a format for Jump");
+        let event_format = format.event_format.expect("Expected Jump to be an event");
+        assert_eq!(event_format.method, "brp_extras/send_event");
+        assert_eq!(event_format.example, format.spawn_format.example);
+        assert!(event_format.buffered);
+
+        // Events without ReflectEvent can't be sent, so they get no event format
+        let format = discover_component_format(app.world(), Land::type_path())
+            .expect("Expected a format for Land");
+        assert!(format.event_format.is_none());
+
+        let format = discover_component_format(app.world(), Transform::type_path())
+            .expect("Expected a format for Transform");
+        assert!(format.event_format.is_none());
+    }
+
     #[test]
     fn test_debug_context() {
         let mut ctx = DebugContext::new();
@@ -4,14 +4,18 @@
 //! and retrieving type information needed for format discovery operations.
 
 use bevy::prelude::*;
-use bevy::reflect::TypeInfo;
+use bevy::reflect::{TypeInfo, TypeRegistration, TypeRegistry};
 use serde_json::{Value, json};
 
+use crate::events::ReflectEvent;
+
 /// Errors that can occur during registry operations
 #[derive(Debug, thiserror::Error)]
 pub enum RegistryError {
     #[error("Type '{type_name}' not found in registry")]
     TypeNotFound { type_name: String },
+    #[error("AppTypeRegistry is not available")]
+    RegistryUnavailable,
 }
 
 impl RegistryError {
@@ -28,11 +32,48 @@ impl RegistryError {
                     )),
                 );
             }
+            Self::RegistryUnavailable => {
+                error_info.insert("reason".to_string(), json!("Type registry not available"));
+                error_info.insert(
+                    "details".to_string(),
+                    json!("The world has no AppTypeRegistry resource"),
+                );
+            }
         }
         error_info
     }
 }
 
+/// Look up a type registration by its full type path
+pub fn find_registration<'a>(
+    registry: &'a TypeRegistry,
+    type_name: &str,
+) -> Result<&'a TypeRegistration, RegistryError> {
+    registry
+        .get_with_type_path(type_name)
+        .ok_or_else(|| RegistryError::TypeNotFound {
+            type_name: type_name.to_string(),
+        })
+}
+
+/// Check whether an event type with `ReflectEvent` data has its `Events<E>` resource
+///
+/// Returns `None` for types that can't be sent as events.
+pub fn event_buffered(
+    world: &World,
+    type_name: &str,
+    debug_info: &mut Vec<String>,
+) -> Result<Option<bool>, RegistryError> {
+    let type_registry = world
+        .get_resource::<AppTypeRegistry>()
+        .ok_or(RegistryError::RegistryUnavailable)?;
+    let buffered = find_registration(&type_registry.read(), type_name)?
+        .data::<ReflectEvent>()
+        .map(|reflect_event| reflect_event.is_buffered(world));
+    debug_info.push(format!("Event buffering of {type_name}: {buffered:?}"));
+    Ok(buffered)
+}
+
 /// Get type info from the type registry with unified error handling
 pub fn get_type_info_from_registry(
     world: &World,
@@ -40,17 +81,20 @@ pub fn get_type_info_from_registry(
     debug_info: &mut Vec<String>,
 ) -> Result<TypeInfo, RegistryError> {
     debug_info.push(format!("Getting type info for: {type_name}"));
-    let type_registry = world.resource::<AppTypeRegistry>();
+    let type_registry = world
+        .get_resource::<AppTypeRegistry>()
+        .ok_or(RegistryError::RegistryUnavailable)?;
 
     // Get type info within a smaller scope to release the registry lock early
     let registry = type_registry.read();
-    if let Some(registration) = registry.get_with_type_path(type_name) {
-        debug_info.push(format!("Found type in registry: {type_name}"));
-        Ok(registration.type_info().clone())
-    } else {
-        debug_info.push(format!("Type not found in registry: {type_name}"));
-        Err(RegistryError::TypeNotFound {
-            type_name: type_name.to_string(),
-        })
+    match find_registration(&registry, type_name) {
+        Ok(registration) => {
+            debug_info.push(format!("Found type in registry: {type_name}"));
+            Ok(registration.type_info().clone())
+        }
+        Err(error) => {
+            debug_info.push(format!("Type not found in registry: {type_name}"));
+            Err(error)
+        }
     }
 }
@@ -0,0 +1,187 @@
+//! Sending reflected app events over BRP
+//!
+//! Events are built from JSON through the type registry, so an event type needs the
+//! [`ReflectEvent`] type data to be sent:
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use bevy_brp_extras::ReflectEvent;
+//!
+//! #[derive(Event, Reflect)]
+//! #[reflect(Event)]
+//! struct SpawnEnemy {
+//!     position: Vec3,
+//! }
+//!
+//! App::new()
+//!     .add_event::<SpawnEnemy>()
+//!     .register_type::<SpawnEnemy>();
+//! ```
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::{FromType, TypeRegistry};
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::Deserialize;
+use serde::de::DeserializeSeed;
+use serde_json::{Value, json};
+
+use crate::reflection::{app_type_registry, registration};
+
+/// Type data for events that can be sent with `brp_extras/send_event`
+///
+/// Add it with `#[reflect(Event)]` on an event type that derives `Reflect`.
+#[derive(Clone)]
+pub struct ReflectEvent {
+    send:        fn(&mut World, &dyn PartialReflect) -> Result<(), BrpError>,
+    is_buffered: fn(&World) -> bool,
+}
+
+impl ReflectEvent {
+    /// Build the event from a reflected value and write it into its `Events<E>` resource
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the value can't be converted to the event type or the event
+    /// hasn't been added with `App::add_event`
+    pub fn send(&self, world: &mut World, event: &dyn PartialReflect) -> Result<(), BrpError> {
+        (self.send)(world, event)
+    }
+
+    /// Whether the event's `Events<E>` resource exists, which `App::add_event` inserts
+    pub fn is_buffered(&self, world: &World) -> bool {
+        (self.is_buffered)(world)
+    }
+}
+
+impl<E: Event + FromReflect + TypePath> FromType<E> for ReflectEvent {
+    fn from_type() -> Self {
+        Self {
+            send:        send_reflected::<E>,
+            is_buffered: World::contains_resource::<Events<E>>,
+        }
+    }
+}
+
+fn send_reflected<E: Event + FromReflect + TypePath>(
+    world: &mut World,
+    event: &dyn PartialReflect,
+) -> Result<(), BrpError> {
+    let event = E::from_reflect(event)
+        .ok_or_else(|| invalid(format!("Value is not a valid {}", E::type_path())))?;
+    let mut events = world
+        .get_resource_mut::<Events<E>>()
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_REQUEST,
+            message: format!(
+                "Events<{}> doesn't exist, add the event with App::add_event",
+                E::type_path()
+            ),
+            data:    None,
+        })?;
+    events.send(event);
+    Ok(())
+}
+
+/// Request parameters for `brp_extras/send_event`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SendEventRequest {
+    /// Full type path of the event type
+    pub event: String,
+    /// Event payload in BRP's JSON format, the same shape used for components
+    pub value: Value,
+}
+
+/// Handler for sending a reflected event
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: SendEventRequest = params
+        .ok_or_else(|| invalid("Missing parameters, expected 'event' and 'value'".to_string()))
+        .and_then(|params| {
+            serde_json::from_value(params)
+                .map_err(|e| invalid(format!("Invalid send_event parameters: {e}")))
+        })?;
+
+    let type_registry = app_type_registry(world)?;
+    let (reflect_event, event) = build_event(&type_registry.read(), &request)?;
+    reflect_event.send(world, event.as_ref())?;
+    debug!("Sent {} event over BRP", request.event);
+
+    Ok(json!({
+        "success": true,
+        "event": request.event,
+        "message": format!("Sent {}", request.event)
+    }))
+}
+
+/// Deserialize the event payload and find the type data that sends it
+fn build_event(
+    registry: &TypeRegistry,
+    request: &SendEventRequest,
+) -> Result<(ReflectEvent, Box<dyn PartialReflect>), BrpError> {
+    let event_registration = registration(registry, &request.event)?;
+    let reflect_event = event_registration
+        .data::<ReflectEvent>()
+        .ok_or_else(|| {
+            invalid(format!(
+                "'{}' can't be sent, add #[reflect(Event)] to the event type",
+                request.event
+            ))
+        })?
+        .clone();
+    let event = TypedReflectDeserializer::new(event_registration, registry)
+        .deserialize(&request.value)
+        .map_err(|e| invalid(format!("Invalid value for {}: {e}", request.event)))?;
+    Ok((reflect_event, event))
+}
+
+const fn invalid(message: String) -> BrpError {
+    BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Event, Reflect, Debug, PartialEq)]
+    #[reflect(Event)]
+    struct Damage {
+        amount: u32,
+        target: String,
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_send_event() {
+        let mut app = App::new();
+        app.add_event::<Damage>().register_type::<Damage>();
+
+        let params = json!({
+            "event": Damage::type_path(),
+            "value": { "amount": 5, "target": "Player" }
+        });
+        handler(In(Some(params)), app.world_mut()).expect("Expected the event to be sent");
+
+        let events = app.world().resource::<Events<Damage>>();
+        let sent: Vec<&Damage> = events.iter_current_update_events().collect();
+        assert_eq!(
+            sent,
+            vec![&Damage {
+                amount: 5,
+                target: "Player".to_string(),
+            }]
+        );
+
+        let params = json!({ "event": Damage::type_path(), "value": { "amount": "five" } });
+        assert!(handler(In(Some(params)), app.world_mut()).is_err());
+        let params = json!({ "event": String::type_path(), "value": "text" });
+        assert!(handler(In(Some(params)), app.world_mut()).is_err());
+        // A world without a type registry is an error, not a panic
+        let params = json!({ "event": Damage::type_path(), "value": { "amount": 5 } });
+        let error = handler(In(Some(params)), &mut World::new()).expect_err("Expected an error");
+        assert_eq!(error.code, error_codes::INTERNAL_ERROR);
+    }
+}
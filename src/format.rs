@@ -16,6 +16,10 @@ pub struct FormatInfo {
     pub spawn_format:  SpawnInfo,
     /// Format information for mutation operations
     pub mutation_info: MutationInfo,
+    /// Format information for sending the type as an event, present for types carrying
+    /// `ReflectEvent` type data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_format:  Option<EventInfo>,
 }
 
 /// Information about how to format data for spawn operations
@@ -27,6 +31,19 @@ pub struct SpawnInfo {
     pub description: String,
 }
 
+/// Information about how a type is used as an event and how to send it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventInfo {
+    /// BRP method that sends the event
+    pub method:      String,
+    /// Example event payload, used as the `value` parameter of `method`
+    pub example:     serde_json::Value,
+    /// Whether the event is buffered in an `Events<E>` resource added with `App::add_event`
+    pub buffered:    bool,
+    /// Description of the expected format
+    pub description: String,
+}
+
 /// Information about available mutation paths and formats
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MutationInfo {
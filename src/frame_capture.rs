@@ -0,0 +1,491 @@
+//! Frame sequence capture for BRP extras
+//!
+//! Captures a run of frames, either a fixed number or every frame for a duration, to numbered
+//! image files in a directory. Once every image has been saved a `manifest.json` is written next
+//! to them listing each file with the frame offset and time it was captured at.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::screenshot::Screenshot;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::output::{check_overwrite, overwrite_allowed, resolve_output_path, write_file};
+use crate::screenshot::{
+    CaptureTarget, CropRect, ScreenshotFormat, resolve_crop, resolve_target, save_capture,
+    spawn_capture,
+};
+
+/// Maximum number of frames a single capture can save
+const MAX_CAPTURED_FRAMES: u32 = 600;
+
+/// Maximum capture duration in milliseconds (1 minute)
+const MAX_CAPTURE_DURATION_MS: u32 = 60_000;
+
+/// Time to wait for outstanding saves after the last frame was requested
+const SAVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// File name of the manifest written into the capture directory
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Request parameters for frame sequence capture
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptureFramesRequest {
+    /// Directory to save the numbered images and manifest to
+    pub directory:   String,
+    /// Number of images to capture
+    #[serde(default)]
+    pub frames:      Option<u32>,
+    /// Capture for this long in milliseconds of real time instead of a fixed number of images
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+    /// Capture every Kth frame
+    #[serde(default = "default_every")]
+    pub every:       u32,
+    /// Image encoding
+    #[serde(default)]
+    pub format:      ScreenshotFormat,
+    /// File name prefix for the numbered images
+    #[serde(default = "default_prefix")]
+    pub prefix:      String,
+    /// Replace the images and manifest of an earlier capture in the same directory, defaults to
+    /// true without an output root and false with one
+    #[serde(default)]
+    pub overwrite:   Option<bool>,
+    /// What to capture and which region of it to keep
+    #[serde(flatten)]
+    pub target:      CaptureTarget,
+}
+
+const fn default_every() -> u32 {
+    1
+}
+
+fn default_prefix() -> String {
+    "frame".to_string()
+}
+
+/// A captured frame listed in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedFrame {
+    /// Sequence number of the image
+    pub index:        u32,
+    /// Frame offset from the start of the capture
+    pub frame:        u32,
+    /// Real time from the start of the capture in milliseconds
+    pub timestamp_ms: u64,
+    /// File name of the image within the capture directory
+    pub file:         String,
+    /// Size of the saved file in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes:   Option<u64>,
+    /// Error message, if the frame wasn't saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error:        Option<String>,
+}
+
+/// Manifest written once a frame capture completes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameManifest {
+    /// Absolute path of the capture directory
+    pub directory:   String,
+    /// Image encoding
+    pub format:      ScreenshotFormat,
+    /// Frame interval between captured images
+    pub every:       u32,
+    /// Number of frames the capture spanned
+    pub frame_count: u32,
+    /// Real time the capture spanned in milliseconds
+    pub duration_ms: u64,
+    /// Captured images in order
+    pub frames:      Vec<CapturedFrame>,
+}
+
+/// When a frame capture stops requesting frames
+#[derive(Debug, Clone, Copy)]
+enum CaptureLimit {
+    Frames(u32),
+    Duration(Duration),
+}
+
+/// Results of the background saves, by image index
+type SaveResults = Arc<Mutex<Vec<(u32, Result<u64, String>)>>>;
+
+/// Resource present while a frame sequence is being captured
+#[derive(Resource)]
+pub struct ActiveFrameCapture {
+    directory:      PathBuf,
+    prefix:         String,
+    format:         ScreenshotFormat,
+    overwrite:      bool,
+    target:         RenderTarget,
+    crop:           Option<CropRect>,
+    every:          u32,
+    limit:          CaptureLimit,
+    frame:          u32,
+    elapsed:        Duration,
+    frames:         Vec<CapturedFrame>,
+    results:        SaveResults,
+    /// Time spent waiting for saves after the last frame was requested
+    finishing_time: Option<Duration>,
+}
+
+/// Handler for frame sequence capture requests
+///
+/// Starts capturing frames and responds immediately. The manifest appears in the directory once
+/// every image has been saved.
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: CaptureFramesRequest = params
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Missing 'directory' parameter".to_string(),
+            data:    None,
+        })
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|e| BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!("Invalid frame capture parameters: {e}"),
+                data:    None,
+            })
+        })?;
+
+    if world.contains_resource::<ActiveFrameCapture>() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_REQUEST,
+            message: "A frame capture is already in progress".to_string(),
+            data:    None,
+        });
+    }
+
+    let limit = capture_limit(&request)?;
+    if request.prefix.is_empty() || request.prefix.contains(['/', '\\']) || request.prefix == ".." {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid file name prefix '{}'", request.prefix),
+            data:    None,
+        });
+    }
+
+    let directory = resolve_output_path(world, &request.directory)?;
+    let overwrite = overwrite_allowed(world, request.overwrite);
+    let manifest = directory.join(MANIFEST_FILE_NAME);
+    check_overwrite(&manifest, overwrite)?;
+    if let Some(frame) = existing_frame(&directory, &request.prefix) {
+        check_overwrite(&frame, overwrite)?;
+    }
+    // A manifest left by an earlier capture would describe the wrong images until this one ends
+    match std::fs::remove_file(&manifest) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(BrpError {
+                code:    error_codes::INTERNAL_ERROR,
+                message: format!("Failed to remove old manifest {}: {e}", manifest.display()),
+                data:    None,
+            });
+        }
+        _ => {}
+    }
+    std::fs::create_dir_all(&directory).map_err(|e| BrpError {
+        code:    error_codes::INTERNAL_ERROR,
+        message: format!(
+            "Failed to create capture directory {}: {e}",
+            directory.display()
+        ),
+        data:    None,
+    })?;
+
+    let (target, target_description) = resolve_target(world, &request.target)?;
+    let crop = resolve_crop(world, &request.target)?;
+
+    info!(
+        "Capturing frames of {target_description} to {}",
+        directory.display()
+    );
+
+    let response = json!({
+        "success": true,
+        "directory": directory.to_string_lossy(),
+        "manifest": manifest.to_string_lossy(),
+        "frames": request.frames,
+        "duration_ms": request.duration_ms,
+        "every": request.every,
+        "format": request.format,
+        "note": format!("Frame capture started. {MANIFEST_FILE_NAME} is written once every image has been saved.")
+    });
+
+    world.insert_resource(ActiveFrameCapture {
+        directory,
+        prefix: request.prefix,
+        format: request.format,
+        overwrite,
+        target,
+        crop,
+        every: request.every,
+        limit,
+        frame: 0,
+        elapsed: Duration::ZERO,
+        frames: Vec::new(),
+        results: SaveResults::default(),
+        finishing_time: None,
+    });
+
+    Ok(response)
+}
+
+/// Validate the requested frame count, duration and interval
+fn capture_limit(request: &CaptureFramesRequest) -> Result<CaptureLimit, BrpError> {
+    let invalid = |message: String| BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    };
+
+    if request.every == 0 {
+        return Err(invalid("'every' must be at least 1".to_string()));
+    }
+
+    match (request.frames, request.duration_ms) {
+        (Some(_), Some(_)) => Err(invalid(
+            "Specify either 'frames' or 'duration_ms', not both".to_string(),
+        )),
+        (None, None) => Err(invalid(
+            "Specify either 'frames' or 'duration_ms'".to_string(),
+        )),
+        (Some(frames), None) if frames == 0 || frames > MAX_CAPTURED_FRAMES => Err(invalid(
+            format!("'frames' must be between 1 and {MAX_CAPTURED_FRAMES}"),
+        )),
+        (Some(frames), None) => Ok(CaptureLimit::Frames(frames)),
+        (None, Some(duration_ms)) if duration_ms == 0 || duration_ms > MAX_CAPTURE_DURATION_MS => {
+            Err(invalid(format!(
+                "'duration_ms' must be between 1 and {MAX_CAPTURE_DURATION_MS}"
+            )))
+        }
+        (None, Some(duration_ms)) => Ok(CaptureLimit::Duration(Duration::from_millis(u64::from(
+            duration_ms,
+        )))),
+    }
+}
+
+/// Find an image of an earlier capture with the same prefix, such as `frame_00000.png`
+fn existing_frame(directory: &Path, prefix: &str) -> Option<PathBuf> {
+    std::fs::read_dir(directory)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(prefix))
+                .and_then(|index| index.strip_prefix('_'))
+                .is_some_and(|index| index.len() == 5 && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+/// System that requests captured frames and writes the manifest once they are saved
+pub fn process_frame_capture(world: &mut World) {
+    let Some(mut capture) = world.remove_resource::<ActiveFrameCapture>() else {
+        return;
+    };
+    let delta = world
+        .get_resource::<Time<Real>>()
+        .map_or(Duration::ZERO, Time::delta);
+
+    if let Some(finishing_time) = capture.finishing_time.as_mut() {
+        *finishing_time += delta;
+    } else {
+        let captured = u32::try_from(capture.frames.len()).unwrap_or(u32::MAX);
+        let within_limit = match capture.limit {
+            CaptureLimit::Frames(frames) => captured < frames,
+            CaptureLimit::Duration(duration) => {
+                capture.elapsed < duration && captured < MAX_CAPTURED_FRAMES
+            }
+        };
+
+        if within_limit {
+            if capture.frame % capture.every == 0 {
+                request_frame(world, &mut capture, captured);
+            }
+            capture.frame = capture.frame.saturating_add(1);
+            capture.elapsed += delta;
+        } else {
+            capture.finishing_time = Some(Duration::ZERO);
+        }
+    }
+
+    let saved = capture.results.lock().map_or(0, |results| results.len());
+    match capture.finishing_time {
+        Some(finishing_time) if saved >= capture.frames.len() || finishing_time >= SAVE_TIMEOUT => {
+            write_manifest(capture);
+        }
+        _ => world.insert_resource(capture),
+    }
+}
+
+/// Spawn a screenshot for the next image of the sequence
+fn request_frame(world: &mut World, capture: &mut ActiveFrameCapture, index: u32) {
+    let file = format!(
+        "{}_{index:05}.{}",
+        capture.prefix,
+        capture.format.extension()
+    );
+    let path = capture.directory.join(&file).to_string_lossy().to_string();
+    let crop = capture.crop;
+    let format = capture.format;
+    let overwrite = capture.overwrite;
+    let results = capture.results.clone();
+
+    spawn_capture(
+        world,
+        Screenshot(capture.target.clone()),
+        format!("Screenshot_{path}"),
+        move |img| {
+            let result = save_capture(img, crop, format, &path, overwrite);
+            if let Err(e) = &result {
+                error!("{e}");
+            }
+            if let Ok(mut results) = results.lock() {
+                results.push((index, result));
+            }
+        },
+    );
+
+    capture.frames.push(CapturedFrame {
+        index,
+        frame: capture.frame,
+        timestamp_ms: u64::try_from(capture.elapsed.as_millis()).unwrap_or(u64::MAX),
+        file,
+        size_bytes: None,
+        error: None,
+    });
+}
+
+/// Fill in the save results and write the manifest into the capture directory
+fn write_manifest(capture: ActiveFrameCapture) {
+    let mut frames = capture.frames;
+    if let Ok(results) = capture.results.lock() {
+        for (index, result) in results.iter() {
+            let Some(frame) = frames.iter_mut().find(|frame| frame.index == *index) else {
+                continue;
+            };
+            match result {
+                Ok(size_bytes) => frame.size_bytes = Some(*size_bytes),
+                Err(e) => frame.error = Some(e.clone()),
+            }
+        }
+    }
+    for frame in &mut frames {
+        if frame.size_bytes.is_none() && frame.error.is_none() {
+            frame.error = Some("Frame was not captured".to_string());
+        }
+    }
+
+    let manifest = FrameManifest {
+        directory: capture.directory.to_string_lossy().to_string(),
+        format: capture.format,
+        every: capture.every,
+        frame_count: capture.frame,
+        duration_ms: u64::try_from(capture.elapsed.as_millis()).unwrap_or(u64::MAX),
+        frames,
+    };
+
+    let path = capture.directory.join(MANIFEST_FILE_NAME);
+    let written = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            write_file(&path, contents.as_bytes(), capture.overwrite).map_err(|e| e.to_string())
+        });
+    match written {
+        Ok(()) => info!(
+            "Frame capture complete - {} images, manifest saved to {}",
+            manifest.frames.len(),
+            path.display()
+        ),
+        Err(e) => error!("Failed to write frame manifest {}: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::window::PrimaryWindow;
+
+    use super::*;
+    use crate::output::unique_temp_dir;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_frame_capture_writes_manifest() {
+        let directory = unique_temp_dir("frame_capture");
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.spawn((Window::default(), PrimaryWindow));
+
+        let params = json!({
+            "directory": directory.to_string_lossy(),
+            "frames": 3,
+            "every": 2,
+            "overwrite": true
+        });
+        handler(In(Some(params.clone())), &mut world).expect("Expected capture to start");
+        assert!(handler(In(Some(params)), &mut world).is_err());
+
+        for _ in 0..6 {
+            process_frame_capture(&mut world);
+        }
+        let capture = world.resource::<ActiveFrameCapture>();
+        let frames: Vec<u32> = capture.frames.iter().map(|frame| frame.frame).collect();
+        assert_eq!(frames, vec![0, 2, 4]);
+
+        // Simulate the background saves completing
+        if let Ok(mut results) = capture.results.lock() {
+            results.extend([(0, Ok(10)), (1, Ok(10)), (2, Err("failed".to_string()))]);
+        }
+        process_frame_capture(&mut world);
+        assert!(!world.contains_resource::<ActiveFrameCapture>());
+
+        let manifest: FrameManifest = serde_json::from_str(
+            &std::fs::read_to_string(directory.join(MANIFEST_FILE_NAME))
+                .expect("Expected manifest"),
+        )
+        .expect("Expected valid manifest");
+        assert_eq!(manifest.frames.len(), 3);
+        assert_eq!(manifest.frames[1].file, "frame_00001.png");
+        assert_eq!(manifest.frames[2].error.as_deref(), Some("failed"));
+
+        // Earlier captures are only replaced with overwrite, which removes the old manifest
+        let params = |overwrite: bool| {
+            json!({
+                "directory": directory.to_string_lossy(),
+                "frames": 1,
+                "overwrite": overwrite
+            })
+        };
+        assert!(handler(In(Some(params(false))), &mut world).is_err());
+        std::fs::remove_file(directory.join(MANIFEST_FILE_NAME)).expect("Expected manifest");
+        std::fs::write(directory.join("frame_00000.png"), []).expect("Expected frame");
+        assert!(handler(In(Some(params(false))), &mut world).is_err());
+        std::fs::write(directory.join(MANIFEST_FILE_NAME), "{}").expect("Expected manifest");
+        handler(In(Some(params(true))), &mut world).expect("Expected capture to start");
+        assert!(!directory.join(MANIFEST_FILE_NAME).exists());
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_capture_limit_validation() {
+        let request = |params: Value| {
+            serde_json::from_value::<CaptureFramesRequest>(params)
+                .map_err(|_| ())
+                .and_then(|request| capture_limit(&request).map_err(|_| ()))
+        };
+
+        assert!(request(json!({ "directory": "d", "frames": 5 })).is_ok());
+        assert!(request(json!({ "directory": "d", "duration_ms": 500, "every": 3 })).is_ok());
+        assert!(request(json!({ "directory": "d" })).is_err());
+        assert!(request(json!({ "directory": "d", "frames": 5, "duration_ms": 500 })).is_err());
+        assert!(request(json!({ "directory": "d", "frames": 5, "every": 0 })).is_err());
+        assert!(request(json!({ "directory": "d", "frames": 601 })).is_err());
+    }
+}
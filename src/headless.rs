@@ -0,0 +1,323 @@
+//! Headless rendering for apps without a window
+//!
+//! With `BrpExtrasPlugin::with_headless` an offscreen image is created at startup and cameras
+//! that target the primary window are redirected to it while no primary window exists. Once a
+//! primary window appears, the redirected cameras target it again. An app
+//! without any camera gets a 2D camera rendering to the image, which is removed again once the
+//! app spawns its own. Screenshot, compare and frame capture methods then capture that image
+//! instead of a window.
+//!
+//! On machines without a GPU, wgpu uses a software adapter such as lavapipe when one is installed.
+//! [`fallback_render_plugin`] forces the software adapter even when a GPU is present, which keeps
+//! CI captures consistent. Apps built without a renderer get an error from capture methods
+//! instead of a request that never completes.
+
+use std::sync::Arc;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::remote::{BrpError, error_codes};
+use bevy::render::RenderPlugin;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::renderer::{RenderAdapterInfo, RenderInstance, WgpuWrapper, initialize_renderer};
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::tasks::block_on;
+use bevy::window::{PrimaryWindow, WindowRef};
+
+/// Resource requesting an offscreen render target of the given size
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct HeadlessConfig {
+    /// Size of the offscreen image in pixels
+    pub size: UVec2,
+}
+
+/// Resource holding the offscreen image that headless cameras render to
+#[derive(Resource, Debug, Clone)]
+pub struct HeadlessTarget {
+    /// Image that cameras render to while no primary window exists
+    pub image: Handle<Image>,
+    /// Size of the image in pixels
+    pub size:  UVec2,
+}
+
+/// Marker for the camera spawned in headless mode when the app has no camera of its own
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct HeadlessCamera;
+
+/// Marker for cameras redirected from the primary window to the offscreen image
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct HeadlessRetargeted;
+
+/// Marker resource inserted when the app was built without a renderer
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct RendererUnavailable;
+
+/// Create the offscreen image and log the adapter it will be rendered with
+#[allow(clippy::needless_pass_by_value)]
+pub fn setup_headless_target(
+    mut commands: Commands,
+    config: Res<HeadlessConfig>,
+    images: Option<ResMut<Assets<Image>>>,
+    adapter: Option<Res<RenderAdapterInfo>>,
+) {
+    let Some(mut images) = images else {
+        warn!("Headless mode needs the render plugins, no offscreen target was created");
+        return;
+    };
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width:                 config.size.x.max(1),
+            height:                config.size.y.max(1),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+
+    commands.insert_resource(HeadlessTarget {
+        image: images.add(image),
+        size:  config.size,
+    });
+
+    if let Some(adapter) = adapter {
+        info!(
+            "Headless rendering at {}x{} using {} ({:?})",
+            config.size.x, config.size.y, adapter.name, adapter.device_type
+        );
+    } else {
+        warn!("Headless mode is enabled but no renderer is available");
+    }
+}
+
+/// Redirect cameras targeting the primary window to the offscreen image while no window exists
+///
+/// Redirected cameras are restored once a primary window exists, unless the app changed their
+/// target in the meantime.
+pub fn retarget_cameras(
+    mut commands: Commands,
+    target: Option<Res<HeadlessTarget>>,
+    primary_window: Query<(), With<PrimaryWindow>>,
+    mut cameras: Query<(Entity, &mut Camera, Has<HeadlessRetargeted>)>,
+) {
+    let Some(target) = target else {
+        return;
+    };
+
+    for (entity, mut camera, retargeted) in &mut cameras {
+        if primary_window.is_empty() {
+            if matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+                camera.target = RenderTarget::Image(target.image.clone().into());
+                commands.entity(entity).insert(HeadlessRetargeted);
+            }
+        } else if retargeted {
+            if matches!(&camera.target, RenderTarget::Image(image) if image.handle == target.image)
+            {
+                camera.target = RenderTarget::Window(WindowRef::Primary);
+            }
+            commands.entity(entity).remove::<HeadlessRetargeted>();
+        }
+    }
+}
+
+/// Spawn a camera rendering to the offscreen image while the app has no camera of its own
+///
+/// The camera is despawned once the app spawns a camera or a primary window appears.
+pub fn ensure_headless_camera(
+    mut commands: Commands,
+    target: Option<Res<HeadlessTarget>>,
+    primary_window: Query<(), With<PrimaryWindow>>,
+    app_cameras: Query<(), (With<Camera>, Without<HeadlessCamera>)>,
+    headless_cameras: Query<Entity, With<HeadlessCamera>>,
+) {
+    let Some(target) = target else {
+        return;
+    };
+    let needs_camera = primary_window.is_empty() && app_cameras.is_empty();
+
+    if needs_camera && headless_cameras.is_empty() {
+        debug!("Spawning a headless camera since the app has no camera");
+        commands.spawn((
+            Name::new("Headless Camera"),
+            HeadlessCamera,
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(target.image.clone().into()),
+                ..default()
+            },
+        ));
+    } else if !needs_camera {
+        for camera in &headless_cameras {
+            commands.entity(camera).despawn();
+        }
+    }
+}
+
+/// Render plugin that uses wgpu's fallback adapter, a software renderer such as lavapipe, even
+/// when a GPU is available
+///
+/// Replace the default render plugin with it in CI to capture with the same renderer on every
+/// machine:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_brp_extras::{BrpExtrasPlugin, fallback_render_plugin};
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins.set(fallback_render_plugin()))
+///     .add_plugins(BrpExtrasPlugin::new().with_headless(1280, 720));
+/// ```
+///
+/// When no fallback adapter is installed the app is built without a renderer, so capture methods
+/// return an error explaining that no renderer is available.
+#[must_use]
+pub fn fallback_render_plugin() -> RenderPlugin {
+    let settings = WgpuSettings::default();
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: settings.backends.unwrap_or_else(wgpu::Backends::all),
+        flags: settings.instance_flags,
+        ..default()
+    });
+    let options = wgpu::RequestAdapterOptions {
+        power_preference:       settings.power_preference,
+        force_fallback_adapter: true,
+        compatible_surface:     None,
+    };
+
+    // Bevy panics when it finds no adapter, so check for one first
+    if block_on(instance.request_adapter(&options)).is_none() {
+        warn!("No fallback adapter found, the app will run without a renderer");
+        return RenderPlugin {
+            render_creation: WgpuSettings {
+                backends: None,
+                ..settings
+            }
+            .into(),
+            ..default()
+        };
+    }
+
+    let (device, queue, adapter_info, adapter) =
+        block_on(initialize_renderer(&instance, &settings, &options));
+    info!("Using fallback adapter {}", adapter_info.name);
+    RenderPlugin {
+        render_creation: RenderCreation::manual(
+            device,
+            queue,
+            adapter_info,
+            adapter,
+            RenderInstance(Arc::new(WgpuWrapper::new(instance))),
+        ),
+        ..default()
+    }
+}
+
+/// Fail with a clear error when the app cannot render anything to capture
+pub fn require_renderer(world: &World) -> Result<(), BrpError> {
+    if world.contains_resource::<RendererUnavailable>() {
+        return Err(BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: "No renderer is available, so nothing can be captured. Add the render \
+                      plugins and install a GPU driver or a software renderer such as lavapipe"
+                .to_string(),
+            data:    None,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_cameras_render_to_headless_target() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Image>>();
+        world.insert_resource(HeadlessConfig {
+            size: UVec2::new(64, 32),
+        });
+        let camera = world.spawn(Camera::default()).id();
+
+        world
+            .run_system_once(setup_headless_target)
+            .expect("Expected setup to run");
+        world
+            .run_system_once(retarget_cameras)
+            .expect("Expected retarget to run");
+
+        let target = world.resource::<HeadlessTarget>();
+        let image = world
+            .resource::<Assets<Image>>()
+            .get(&target.image)
+            .expect("Expected offscreen image");
+        assert_eq!(image.size(), UVec2::new(64, 32));
+        assert!(matches!(
+            world.get::<Camera>(camera).expect("Expected camera").target,
+            RenderTarget::Image(_)
+        ));
+
+        // The camera targets the window again once one exists
+        world.spawn((Window::default(), PrimaryWindow));
+        world
+            .run_system_once(retarget_cameras)
+            .expect("Expected retarget to run");
+        assert!(matches!(
+            world.get::<Camera>(camera).expect("Expected camera").target,
+            RenderTarget::Window(WindowRef::Primary)
+        ));
+        assert!(!world.entity(camera).contains::<HeadlessRetargeted>());
+
+        assert!(require_renderer(&world).is_ok());
+        world.insert_resource(RendererUnavailable);
+        assert!(require_renderer(&world).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_headless_camera_only_without_app_camera() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Image>>();
+        world.insert_resource(HeadlessConfig {
+            size: UVec2::new(64, 32),
+        });
+        world
+            .run_system_once(setup_headless_target)
+            .expect("Expected setup to run");
+
+        let headless_cameras = |world: &mut World| {
+            world
+                .query_filtered::<&Camera, With<HeadlessCamera>>()
+                .iter(world)
+                .map(|camera| matches!(camera.target, RenderTarget::Image(_)))
+                .collect::<Vec<_>>()
+        };
+
+        world
+            .run_system_once(ensure_headless_camera)
+            .expect("Expected the camera system to run");
+        assert_eq!(headless_cameras(&mut world), vec![true]);
+
+        // A second run keeps the single headless camera
+        world
+            .run_system_once(ensure_headless_camera)
+            .expect("Expected the camera system to run");
+        assert_eq!(headless_cameras(&mut world).len(), 1);
+
+        world.spawn(Camera::default());
+        world
+            .run_system_once(ensure_headless_camera)
+            .expect("Expected the camera system to run");
+        assert!(headless_cameras(&mut world).is_empty());
+    }
+}
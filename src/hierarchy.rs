@@ -0,0 +1,322 @@
+//! Entity hierarchy dump over BRP
+//!
+//! Walks `Children` from every root entity (entities without `ChildOf`), or from a single
+//! entity, and reports each node with its `Name`, component short names and optionally the
+//! values of selected components.
+
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::reflection::{app_type_registry, registration, to_json};
+
+/// Deepest level reported below the starting entities, also applied when `max_depth` is omitted
+const MAX_HIERARCHY_DEPTH: u32 = 100;
+
+/// Request parameters for `brp_extras/hierarchy`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HierarchyRequest {
+    /// Entity to start from instead of every root entity
+    pub entity:        Option<Entity>,
+    /// Maximum depth below the starting entities, where 0 returns only the starting entities,
+    /// capped at 100
+    pub max_depth:     Option<u32>,
+    /// Component type paths whose values are included for each node that has them
+    #[serde(default)]
+    pub values:        Vec<String>,
+    /// Only keep nodes with all of these component type paths, plus their ancestors
+    #[serde(default)]
+    pub with:          Vec<String>,
+    /// Only keep nodes whose `Name` contains this text, plus their ancestors
+    pub name_contains: Option<String>,
+}
+
+/// An entity in the hierarchy dump
+#[derive(Debug, Clone, Serialize)]
+pub struct HierarchyNode {
+    /// Entity ID
+    pub entity:      Entity,
+    /// The entity's `Name`, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name:        Option<String>,
+    /// Short names of the entity's components, sorted
+    pub components:  Vec<String>,
+    /// Values of the requested components, keyed by type path
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub values:      Map<String, Value>,
+    /// Number of direct children, including children left out by depth limits or filters
+    pub child_count: usize,
+    /// Children that are within the depth limit and pass the filters
+    pub children:    Vec<Self>,
+}
+
+/// Components resolved from the request's type paths
+struct Selection {
+    values: Vec<(String, ReflectComponent)>,
+    with:   Option<Vec<ComponentId>>,
+}
+
+/// Handler for dumping the entity hierarchy
+#[allow(clippy::needless_pass_by_ref_mut)]
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: HierarchyRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid hierarchy parameters: {e}"),
+            data:    None,
+        })?,
+        None => HierarchyRequest::default(),
+    };
+
+    let type_registry = app_type_registry(world)?;
+    let nodes = build_hierarchy(world, &type_registry.read(), &request)?;
+    let node_count = nodes.iter().map(count_nodes).sum::<usize>();
+
+    Ok(json!({
+        "success": true,
+        "roots": nodes,
+        "node_count": node_count
+    }))
+}
+
+/// Build the tree for the requested starting entities
+fn build_hierarchy(
+    world: &World,
+    registry: &TypeRegistry,
+    request: &HierarchyRequest,
+) -> Result<Vec<HierarchyNode>, BrpError> {
+    let selection = select_components(world, registry, request)?;
+
+    let starts = if let Some(entity) = request.entity {
+        if world.get_entity(entity).is_err() {
+            return Err(BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!("Entity {entity} doesn't exist"),
+                data:    None,
+            });
+        }
+        vec![entity]
+    } else {
+        let mut roots: Vec<Entity> = world
+            .iter_entities()
+            .filter(|entity| !entity.contains::<ChildOf>())
+            .map(|entity| entity.id())
+            .collect();
+        roots.sort();
+        roots
+    };
+
+    Ok(starts
+        .into_iter()
+        .filter_map(|entity| build_node(world, registry, request, &selection, entity, 0))
+        .collect())
+}
+
+/// Resolve the requested component type paths
+fn select_components(
+    world: &World,
+    registry: &TypeRegistry,
+    request: &HierarchyRequest,
+) -> Result<Selection, BrpError> {
+    let values = request
+        .values
+        .iter()
+        .map(|type_path| {
+            let reflect_component = registration(registry, type_path)?
+                .data::<ReflectComponent>()
+                .ok_or_else(|| BrpError {
+                    code:    error_codes::INVALID_PARAMS,
+                    message: format!("'{type_path}' is not a reflected component"),
+                    data:    None,
+                })?;
+            Ok((type_path.clone(), reflect_component.clone()))
+        })
+        .collect::<Result<Vec<_>, BrpError>>()?;
+
+    // A component that was never added to an entity has no ID, so nothing can match
+    let with = request
+        .with
+        .iter()
+        .map(|type_path| {
+            registration(registry, type_path)
+                .map(|registration| world.components().get_id(registration.type_id()))
+        })
+        .collect::<Result<Option<Vec<_>>, BrpError>>()?;
+
+    Ok(Selection { values, with })
+}
+
+/// Build a node and its children, returning `None` when neither it nor any descendant passes
+/// the filters
+fn build_node(
+    world: &World,
+    registry: &TypeRegistry,
+    request: &HierarchyRequest,
+    selection: &Selection,
+    entity: Entity,
+    depth: u32,
+) -> Option<HierarchyNode> {
+    let entity_ref = world.get_entity(entity).ok()?;
+    let name = entity_ref
+        .get::<Name>()
+        .map(|name| name.as_str().to_string());
+
+    let child_entities: Vec<Entity> = entity_ref
+        .get::<Children>()
+        .map(|children| children.iter().collect())
+        .unwrap_or_default();
+    let max_depth = request
+        .max_depth
+        .map_or(MAX_HIERARCHY_DEPTH, |max| max.min(MAX_HIERARCHY_DEPTH));
+    let children: Vec<HierarchyNode> = if depth >= max_depth {
+        Vec::new()
+    } else {
+        child_entities
+            .iter()
+            .filter_map(|&child| build_node(world, registry, request, selection, child, depth + 1))
+            .collect()
+    };
+
+    let matches_with = selection
+        .with
+        .as_ref()
+        .is_some_and(|ids| ids.iter().all(|&id| entity_ref.contains_id(id)));
+    let matches_name = request
+        .name_contains
+        .as_deref()
+        .is_none_or(|text| name.as_deref().is_some_and(|name| name.contains(text)));
+    if !(matches_with && matches_name) && children.is_empty() {
+        return None;
+    }
+
+    let mut components: Vec<String> = world
+        .inspect_entity(entity)
+        .map(|infos| infos.map(|info| short_name(info.name())).collect())
+        .unwrap_or_default();
+    components.sort();
+
+    let values = selection
+        .values
+        .iter()
+        .filter_map(|(type_path, reflect_component)| {
+            let value = reflect_component.reflect(entity_ref)?;
+            to_json(value.as_partial_reflect(), registry)
+                .ok()
+                .map(|value| (type_path.clone(), value))
+        })
+        .collect();
+
+    Some(HierarchyNode {
+        entity,
+        name,
+        components,
+        values,
+        child_count: child_entities.len(),
+        children,
+    })
+}
+
+fn count_nodes(node: &HierarchyNode) -> usize {
+    1 + node.children.iter().map(count_nodes).sum::<usize>()
+}
+
+/// Shorten a type name by dropping module paths, including inside generic arguments
+fn short_name(type_name: &str) -> String {
+    let mut short = String::with_capacity(type_name.len());
+    let mut segment = String::new();
+    for character in type_name.chars() {
+        if character.is_alphanumeric() || character == '_' || character == ':' {
+            segment.push(character);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or_default());
+            segment.clear();
+            short.push(character);
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_name() {
+        assert_eq!(
+            short_name("bevy_transform::components::transform::Transform"),
+            "Transform"
+        );
+        assert_eq!(
+            short_name("bevy_state::state::resources::State<my_game::GameState>"),
+            "State<GameState>"
+        );
+        assert_eq!(short_name("Name"), "Name");
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_hierarchy_filters_and_depth() {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Transform>();
+
+        let root = world.spawn(Name::new("Root")).id();
+        let menu = world.spawn((Name::new("Menu"), ChildOf(root))).id();
+        world.spawn((Name::new("PlayButton"), Transform::default(), ChildOf(menu)));
+        world.spawn((Name::new("Decoration"), ChildOf(root)));
+
+        let request = HierarchyRequest {
+            with: vec![Transform::type_path().to_string()],
+            values: vec![Transform::type_path().to_string()],
+            ..default()
+        };
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let nodes = build_hierarchy(&world, &registry.read(), &request).expect("Expected a tree");
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].name.as_deref(), Some("Root"));
+        assert_eq!(nodes[0].child_count, 2);
+        let button = &nodes[0].children[0].children[0];
+        assert_eq!(button.name.as_deref(), Some("PlayButton"));
+        assert!(button.components.contains(&"Transform".to_string()));
+        assert!(button.values.contains_key(Transform::type_path()));
+
+        let request = HierarchyRequest {
+            entity: Some(root),
+            max_depth: Some(1),
+            ..default()
+        };
+        let nodes = build_hierarchy(&world, &registry.read(), &request).expect("Expected a tree");
+        assert_eq!(nodes[0].children.len(), 2);
+        assert!(
+            nodes[0]
+                .children
+                .iter()
+                .all(|child| child.children.is_empty())
+        );
+        assert_eq!(nodes.iter().map(count_nodes).sum::<usize>(), 3);
+
+        // Deep hierarchies stop at the depth cap even without max_depth
+        let top = world.spawn_empty().id();
+        let mut parent = top;
+        for _ in 0..150 {
+            parent = world.spawn(ChildOf(parent)).id();
+        }
+        let request = HierarchyRequest {
+            entity: Some(top),
+            ..default()
+        };
+        let nodes = build_hierarchy(&world, &registry.read(), &request).expect("Expected a tree");
+        assert_eq!(
+            nodes.iter().map(count_nodes).sum::<usize>(),
+            MAX_HIERARCHY_DEPTH as usize + 1
+        );
+    }
+}
@@ -1,7 +1,6 @@
 //! Keyboard input simulation for BRP extras
 
 use std::str::FromStr;
-use std::time::Duration;
 
 use bevy::input::ButtonState;
 use bevy::input::keyboard::KeyCode;
@@ -11,8 +10,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use strum_macros::{Display, EnumIter, EnumString};
 
-/// Maximum duration for holding keys in milliseconds (1 minute)
-const MAX_KEY_DURATION_MS: u32 = 60_000;
+use crate::recording::{ActiveInputRecording, cancel_replay, send_injected};
+use crate::timing::{InputClock, InputClockMode, TimeSource, resolve_duration};
+use crate::touch::cancel_touch_sequences;
+use crate::ui_click::release_pending_clicks;
 
 /// Default duration for holding keys in milliseconds
 const DEFAULT_KEY_DURATION_MS: u32 = 100;
@@ -21,9 +22,11 @@ const DEFAULT_KEY_DURATION_MS: u32 = 100;
 #[derive(Component)]
 pub struct TimedKeyRelease {
     /// The key codes to release
-    pub keys:  Vec<KeyCode>,
-    /// Timer tracking the remaining duration
-    pub timer: Timer,
+    pub keys:          Vec<KeyCode>,
+    /// Clock tracking how long the keys have been held
+    pub clock:         InputClock,
+    /// How long to hold the keys, in the clock's unit
+    pub release_after: u32,
 }
 
 /// Wrapper enum for Bevy's `KeyCode` with strum derives for string conversion
@@ -375,25 +378,78 @@ impl KeyCodeWrapper {
 #[derive(Debug, Deserialize)]
 pub struct SendKeysRequest {
     /// Array of key codes to send
-    pub keys:        Vec<String>,
+    pub keys:            Vec<String>,
     /// Duration in milliseconds to hold the keys before releasing
-    #[serde(default = "default_duration")]
-    pub duration_ms: u32,
-}
-
-const fn default_duration() -> u32 {
-    DEFAULT_KEY_DURATION_MS
+    pub duration_ms:     Option<u32>,
+    /// Duration in frames to hold the keys before releasing, instead of `duration_ms`
+    pub duration_frames: Option<u32>,
+    /// Time source for `duration_ms`
+    #[serde(default)]
+    pub time_source:     TimeSource,
 }
 
 /// Response structure for `send_keys`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendKeysResponse {
     /// Whether the operation was successful
-    pub success:     bool,
+    pub success:         bool,
     /// List of keys that were sent
-    pub keys_sent:   Vec<String>,
-    /// Duration in milliseconds the keys were held
-    pub duration_ms: u32,
+    pub keys_sent:       Vec<String>,
+    /// Duration in milliseconds the keys were held, absent when frame timing was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms:     Option<u32>,
+    /// Duration in frames the keys were held, if frame timing was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_frames: Option<u32>,
+}
+
+/// An injected key press that has not been released yet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingKeyRelease {
+    /// Entity holding the `TimedKeyRelease` component
+    pub entity:           Entity,
+    /// Keys that will be released when the hold finishes
+    pub keys:             Vec<String>,
+    /// Time left before the keys are released in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_ms:     Option<u32>,
+    /// Frames left before the keys are released
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_frames: Option<u32>,
+}
+
+/// Response structure for `input_state`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputStateResponse {
+    /// Whether the operation was successful
+    pub success:               bool,
+    /// Injected key presses that are still waiting to be released
+    pub pending_releases:      Vec<PendingKeyRelease>,
+    /// Keys currently pressed according to `ButtonInput<KeyCode>`
+    pub pressed_keys:          Vec<String>,
+    /// Keys pressed this frame according to `ButtonInput<KeyCode>`
+    pub just_pressed_keys:     Vec<String>,
+    /// Keys released this frame according to `ButtonInput<KeyCode>`
+    pub just_released_keys:    Vec<String>,
+    /// Mouse buttons currently pressed according to `ButtonInput<MouseButton>`
+    pub pressed_mouse_buttons: Vec<String>,
+}
+
+/// Response structure for `release_all`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseAllResponse {
+    /// Whether the operation was successful
+    pub success:                  bool,
+    /// Keys that release events were sent for
+    pub keys_released:            Vec<String>,
+    /// Number of pending timed releases that were cleared
+    pub pending_releases_cleared: usize,
+    /// Number of pending touch sequences that were cleared
+    pub touch_sequences_cleared:  usize,
+    /// Number of `click_ui` mouse presses that were released
+    pub clicks_released:          usize,
+    /// Whether an input replay was stopped
+    pub replay_stopped:           bool,
 }
 
 /// Validate key codes and return the parsed key codes
@@ -476,41 +532,160 @@ pub fn send_keys_handler(In(params): In<Option<Value>>, world: &mut World) -> Br
     let key_codes: Vec<KeyCode> = validated_keys.iter().map(|(_, kc)| *kc).collect();
 
     // Validate duration doesn't exceed maximum
-    if request.duration_ms > MAX_KEY_DURATION_MS {
-        return Err(BrpError {
-            code:    error_codes::INVALID_PARAMS,
-            message: format!(
-                "Duration {}ms exceeds maximum allowed duration of {}ms (1 minute)",
-                request.duration_ms, MAX_KEY_DURATION_MS
-            ),
-            data:    None,
-        });
-    }
+    let (clock_mode, release_after) = resolve_duration(
+        request.duration_ms,
+        request.duration_frames,
+        DEFAULT_KEY_DURATION_MS,
+        request.time_source,
+    )?;
 
     // Always send press events first
     let press_events = create_keyboard_events(&key_codes, true);
     for event in press_events {
-        world.send_event(event);
+        send_injected(world, event);
     }
 
     // Always spawn an entity to handle the timed release
     if !key_codes.is_empty() {
         world.spawn(TimedKeyRelease {
-            keys:  key_codes,
-            timer: Timer::new(
-                Duration::from_millis(u64::from(request.duration_ms)),
-                TimerMode::Once,
-            ),
+            keys: key_codes,
+            clock: InputClock::new(clock_mode),
+            release_after,
         });
     }
 
+    let (duration_ms, duration_frames) = match clock_mode {
+        InputClockMode::Milliseconds(_) => (Some(release_after), None),
+        InputClockMode::Frames => (None, Some(release_after)),
+    };
+
     Ok(json!(SendKeysResponse {
-        success:     true,
-        keys_sent:   valid_key_strings,
-        duration_ms: request.duration_ms,
+        success: true,
+        keys_sent: valid_key_strings,
+        duration_ms,
+        duration_frames,
     }))
 }
 
+/// Handler for `input_state` requests
+///
+/// Reports injected keys that are still held along with the app's current button input state
+#[allow(clippy::unnecessary_wraps)]
+pub fn input_state_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let pending_releases = world
+        .query::<(Entity, &TimedKeyRelease)>()
+        .iter(world)
+        .map(|(entity, timed_release)| {
+            let remaining = timed_release.clock.remaining(timed_release.release_after);
+            let (remaining_ms, remaining_frames) = match timed_release.clock.mode {
+                InputClockMode::Milliseconds(_) => (Some(remaining), None),
+                InputClockMode::Frames => (None, Some(remaining)),
+            };
+            PendingKeyRelease {
+                entity,
+                keys: key_code_names(&timed_release.keys),
+                remaining_ms,
+                remaining_frames,
+            }
+        })
+        .collect();
+
+    let (pressed_keys, just_pressed_keys, just_released_keys) = world
+        .get_resource::<ButtonInput<KeyCode>>()
+        .map(|input| {
+            (
+                input.get_pressed().map(|key| format!("{key:?}")).collect(),
+                input
+                    .get_just_pressed()
+                    .map(|key| format!("{key:?}"))
+                    .collect(),
+                input
+                    .get_just_released()
+                    .map(|key| format!("{key:?}"))
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+
+    let pressed_mouse_buttons = world
+        .get_resource::<ButtonInput<MouseButton>>()
+        .map(|input| {
+            input
+                .get_pressed()
+                .map(|button| format!("{button:?}"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(json!(InputStateResponse {
+        success: true,
+        pending_releases,
+        pressed_keys,
+        just_pressed_keys,
+        just_released_keys,
+        pressed_mouse_buttons,
+    }))
+}
+
+/// Handler for `release_all` requests
+///
+/// Immediately sends release events for every injected key that is still held and removes all
+/// pending timed releases. Pending touch sequences are removed with their touches canceled, mouse
+/// buttons pressed by `click_ui` are released and an input replay in progress is stopped.
+#[allow(clippy::unnecessary_wraps)]
+pub fn release_all_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let pending: Vec<(Entity, Vec<KeyCode>)> = world
+        .query::<(Entity, &TimedKeyRelease)>()
+        .iter(world)
+        .map(|(entity, timed_release)| (entity, timed_release.keys.clone()))
+        .collect();
+
+    let mut key_codes: Vec<KeyCode> = Vec::new();
+    for (_, keys) in &pending {
+        for key_code in keys {
+            if !key_codes.contains(key_code) {
+                key_codes.push(*key_code);
+            }
+        }
+    }
+
+    for event in create_keyboard_events(&key_codes, false) {
+        send_injected(world, event);
+    }
+
+    for (entity, _) in &pending {
+        world.despawn(*entity);
+    }
+
+    let touch_sequences_cleared = cancel_touch_sequences(world);
+    let clicks_released = release_pending_clicks(world);
+    let replay_stopped = cancel_replay(world).is_some();
+
+    info!(
+        "Released {} injected keys from {} pending releases, cleared {touch_sequences_cleared} \
+         touch sequences and released {clicks_released} clicks",
+        key_codes.len(),
+        pending.len()
+    );
+
+    Ok(json!(ReleaseAllResponse {
+        success: true,
+        keys_released: key_code_names(&key_codes),
+        pending_releases_cleared: pending.len(),
+        touch_sequences_cleared,
+        clicks_released,
+        replay_stopped,
+    }))
+}
+
+/// Convert key codes to their string names (e.g., "`KeyA`")
+fn key_code_names(key_codes: &[KeyCode]) -> Vec<String> {
+    key_codes
+        .iter()
+        .map(|key_code| format!("{key_code:?}"))
+        .collect()
+}
+
 /// Information about a key code
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KeyCodeInfo {
@@ -531,14 +706,16 @@ fn parse_key_code(s: &str) -> Result<KeyCode, String> {
 #[allow(clippy::needless_pass_by_value)]
 pub fn process_timed_key_releases(
     mut commands: Commands,
-    time: Res<Time>,
+    virtual_time: Res<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
     mut query: Query<(Entity, &mut TimedKeyRelease)>,
     mut keyboard_events: EventWriter<bevy::input::keyboard::KeyboardInput>,
+    mut recording: Option<ResMut<ActiveInputRecording>>,
 ) {
     for (entity, mut timed_release) in &mut query {
-        timed_release.timer.tick(time.delta());
+        timed_release.clock.tick(&virtual_time, &real_time);
 
-        if timed_release.timer.finished() {
+        if timed_release.clock.has_reached(timed_release.release_after) {
             // Send release events for all keys
             for &key_code in &timed_release.keys {
                 let event = bevy::input::keyboard::KeyboardInput {
@@ -551,7 +728,10 @@ pub fn process_timed_key_releases(
                     repeat: false,
                     text: None,
                 };
-                keyboard_events.write(event);
+                let id = keyboard_events.write(event);
+                if let Some(recording) = recording.as_mut() {
+                    recording.skip(id);
+                }
             }
 
             // Remove the component after releasing
@@ -685,7 +865,7 @@ mod tests {
                 assert!(response.success);
                 assert_eq!(response.keys_sent.len(), 1);
                 assert_eq!(response.keys_sent[0], key);
-                assert_eq!(response.duration_ms, 100); // default duration
+                assert_eq!(response.duration_ms, Some(100)); // default duration
             }
         }
     }
@@ -739,7 +919,7 @@ mod tests {
         if let Ok(response_value) = default_result {
             let response: SendKeysResponse =
                 serde_json::from_value(response_value).expect("Failed to deserialize response");
-            assert_eq!(response.duration_ms, 100); // default duration
+            assert_eq!(response.duration_ms, Some(100)); // default duration
             assert_eq!(response.keys_sent.len(), 2);
         }
 
@@ -755,7 +935,7 @@ mod tests {
         if let Ok(response_value) = custom_result {
             let response: SendKeysResponse =
                 serde_json::from_value(response_value).expect("Failed to deserialize response");
-            assert_eq!(response.duration_ms, 500);
+            assert_eq!(response.duration_ms, Some(500));
             assert_eq!(response.keys_sent.len(), 2);
         }
     }
@@ -846,6 +1026,117 @@ mod tests {
         );
     }
 
+    /// Test that `input_state` reports injected keys that are still held
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_input_state_reports_pending_releases() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+
+        let params = json!({
+            "keys": ["KeyA", "ShiftLeft"],
+            "duration_ms": 5_000
+        });
+        assert!(send_keys_handler(In(Some(params)), app.world_mut()).is_ok());
+
+        let result = input_state_handler(In(None), app.world_mut());
+        let response: InputStateResponse =
+            serde_json::from_value(result.expect("Expected success but got error"))
+                .expect("Failed to deserialize response");
+
+        assert!(response.success);
+        assert_eq!(response.pending_releases.len(), 1);
+        assert_eq!(response.pending_releases[0].keys, vec!["KeyA", "ShiftLeft"]);
+        assert!(
+            response.pending_releases[0]
+                .remaining_ms
+                .is_some_and(|remaining| remaining <= 5_000)
+        );
+    }
+
+    /// Test that `release_all` clears every pending release
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_release_all_clears_pending_releases() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin));
+
+        for keys in [json!(["KeyA", "Space"]), json!(["Space", "Enter"])] {
+            let params = json!({ "keys": keys, "duration_ms": 60_000 });
+            assert!(send_keys_handler(In(Some(params)), app.world_mut()).is_ok());
+        }
+        let params = json!({ "gesture": "long_press", "position": [10.0, 20.0] });
+        assert!(crate::touch::send_gesture_handler(In(Some(params)), app.world_mut()).is_ok());
+        app.update();
+
+        let result = release_all_handler(In(None), app.world_mut());
+        let response: ReleaseAllResponse =
+            serde_json::from_value(result.expect("Expected success but got error"))
+                .expect("Failed to deserialize response");
+
+        assert!(response.success);
+        assert_eq!(response.pending_releases_cleared, 2);
+        assert_eq!(response.keys_released, vec!["KeyA", "Space", "Enter"]);
+        assert_eq!(response.touch_sequences_cleared, 1);
+        assert!(!response.replay_stopped);
+
+        let mut query = app.world_mut().query::<&TimedKeyRelease>();
+        assert_eq!(query.iter(app.world()).count(), 0);
+        let mut query = app.world_mut().query::<&crate::touch::TimedTouchSequence>();
+        assert_eq!(query.iter(app.world()).count(), 0);
+
+        app.update();
+        let touches = app.world().resource::<bevy::input::touch::Touches>();
+        assert!(touches.get_pressed(0).is_none());
+        assert!(touches.just_canceled(0));
+    }
+
+    /// Test that keys held for a number of frames release on the expected frame
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_frame_duration_releases_after_frames() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin))
+            .add_systems(Update, process_timed_key_releases);
+
+        let params = json!({
+            "keys": ["KeyW"],
+            "duration_frames": 3
+        });
+        let response: SendKeysResponse = serde_json::from_value(
+            send_keys_handler(In(Some(params)), app.world_mut())
+                .expect("Expected success but got error"),
+        )
+        .expect("Failed to deserialize response");
+        assert_eq!(response.duration_frames, Some(3));
+        assert_eq!(response.duration_ms, None);
+
+        for _ in 0..3 {
+            app.update();
+            let input = app.world().resource::<ButtonInput<KeyCode>>();
+            assert!(input.pressed(KeyCode::KeyW));
+        }
+
+        app.update();
+        let input = app.world().resource::<ButtonInput<KeyCode>>();
+        assert!(!input.pressed(KeyCode::KeyW));
+    }
+
+    /// Test that both `duration_ms` and `duration_frames` cannot be given
+    #[test]
+    fn test_duration_ms_and_frames_rejected() {
+        let mut app = App::new();
+
+        let params = json!({
+            "keys": ["KeyA"],
+            "duration_ms": 100,
+            "duration_frames": 5
+        });
+        let result = send_keys_handler(In(Some(params)), app.world_mut());
+
+        assert!(result.is_err());
+    }
+
     /// Test that empty key array does not create `TimedKeyRelease`
     #[test]
     fn test_empty_keys_no_timed_release() {
@@ -17,28 +17,97 @@
 //! ```
 //!
 //! This will add the following BRP methods to your app:
-//! - `brp_extras/screenshot`: Capture a screenshot
-//! - `brp_extras/shutdown`: Gracefully shutdown the app
+//! - `brp_extras/screenshot`: Capture a screenshot, optionally returned inline as base64
+//! - `brp_extras/screenshot_status`: Report the progress of a screenshot by request ID
+//! - `brp_extras/compare_screenshot`: Compare a screenshot with a baseline image
+//! - `brp_extras/capture_frames`: Capture a sequence of frames to numbered images
+//! - `brp_extras/shutdown`: Gracefully shutdown the app with an optional exit code and delay
+//! - `brp_extras/cancel_shutdown`: Cancel a pending shutdown
+//! - `brp_extras/pause`: Pause virtual time while frames and BRP requests keep running
+//! - `brp_extras/resume`: Resume virtual time
+//! - `brp_extras/step`: Advance a number of frames or fixed timestep ticks, then pause
+//! - `brp_extras/time_settings`: Read and set virtual time speed and the fixed timestep
+//! - `brp_extras/wait_for`: Respond once a condition holds in the app, or fail after a timeout
+//! - `brp_extras/list_states`: List registered states with their current values
+//! - `brp_extras/set_state`: Request a state transition by writing `NextState<S>`
+//! - `brp_extras/send_event`: Send a reflected app event built from JSON
+//! - `brp_extras/hierarchy`: Dump the entity hierarchy with names and component summaries
+//! - `brp_extras/find_entities`: Find entities by `Name`, `Name` path, glob and components
+//! - `brp_extras/click_ui`: Click a `bevy_ui` node by entity or `Name`
+//! - `brp_extras/ui_tree`: Dump the `bevy_ui` layout tree with layout, visibility and text
 //! - `brp_extras/discover_format`: Discover component format information
 //! - `brp_extras/send_keys`: Send keyboard input
+//! - `brp_extras/input_state`: Report held keys and button input state
+//! - `brp_extras/release_all`: Release every injected key and touch and stop input replays
+//! - `brp_extras/send_touch`: Send touch input
+//! - `brp_extras/send_gesture`: Send a tap, long press, swipe or pinch gesture
+//! - `brp_extras/start_input_recording`: Start recording keyboard, mouse and gamepad input
+//! - `brp_extras/stop_input_recording`: Stop recording and return the input script
+//! - `brp_extras/replay_input`: Replay a recorded input script
+//! - `brp_extras/stop_input_replay`: Cancel an input replay in progress
 
+mod compare;
 mod debug_mode;
+mod deferred;
 mod discovery;
+mod events;
 mod format;
+mod frame_capture;
+mod headless;
+mod hierarchy;
 mod keyboard;
-
+mod output;
 mod plugin;
+mod recording;
+mod reflection;
 mod screenshot;
+mod selector;
 mod shutdown;
+mod states;
+mod time_control;
+mod timing;
+mod touch;
+mod ui_click;
+mod ui_tree;
+mod wait_for;
 
+pub use compare::{CompareScreenshotRequest, CompareScreenshotResponse};
 pub use discovery::{
     discover_component_format, discover_multiple_formats, get_common_component_types,
 };
-pub use format::{FieldInfo, FormatInfo, MutationInfo, SpawnInfo};
+pub use events::{ReflectEvent, SendEventRequest};
+pub use format::{EventInfo, FieldInfo, FormatInfo, MutationInfo, SpawnInfo};
+pub use frame_capture::{CaptureFramesRequest, CapturedFrame, FrameManifest};
+pub use headless::{HeadlessCamera, HeadlessRetargeted, HeadlessTarget, fallback_render_plugin};
+pub use hierarchy::{HierarchyNode, HierarchyRequest};
 pub use keyboard::{
-    KeyCodeInfo, KeyCodeWrapper, SendKeysRequest, SendKeysResponse, TimedKeyRelease,
+    InputStateResponse, KeyCodeInfo, KeyCodeWrapper, PendingKeyRelease, ReleaseAllResponse,
+    SendKeysRequest, SendKeysResponse, TimedKeyRelease,
 };
 pub use plugin::BrpExtrasPlugin;
+pub use recording::{
+    InputScript, RecordedInput, RecordedInputEvent, ReplayInputRequest, ReplayTiming,
+};
+pub use screenshot::{
+    CaptureTarget, CropRect, CropRequest, ImageTarget, InlineScreenshotResponse, ScreenshotFormat,
+    ScreenshotRequest, ScreenshotStatus, ScreenshotStatusResponse, WindowTarget,
+};
+pub use selector::{FindEntitiesRequest, FoundEntity};
+pub use shutdown::{BrpShutdownRequested, ShutdownHooks, ShutdownRequest};
+pub use states::{SetStateRequest, StateInfo};
+pub use time_control::{
+    PendingStep, StepRequest, TimeSettings, TimeSettingsRequest, TimeSettingsResponse,
+};
+pub use timing::{InputClock, InputClockMode, TimeSource};
+pub use touch::{
+    Gesture, GestureRequest, ScheduledTouch, SendTouchRequest, SendTouchResponse,
+    TimedTouchSequence, TouchEventRequest,
+};
+pub use ui_click::{ClickUiRequest, ClickUiResponse, PendingClickRelease};
+pub use ui_tree::{Clipping, UiNodeInfo, UiTreeRequest};
+pub use wait_for::{
+    ComponentValueCondition, EntityFilter, StateCondition, WaitCondition, WaitForRequest,
+};
 
 /// Default port for remote control connections
 ///
@@ -0,0 +1,243 @@
+//! Output paths for files written by BRP extras
+//!
+//! When `BrpExtrasPlugin::with_output_root` is set, every file a request writes, along with the
+//! baseline images it compares against, must resolve to a location inside that root. Relative paths
+//! are resolved against the root, absolute paths outside it and paths containing `..` are rejected.
+//! Symlinks are resolved before the check, so a link inside the root can't point a write outside of
+//! it. Without a root, paths resolve against the current directory as before.
+//!
+//! Existing files are replaced by default when there is no root, and only with `overwrite` when
+//! there is one.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::remote::{BrpError, error_codes};
+
+/// Resource holding the directory that written files are confined to, if any
+#[derive(Resource, Debug, Clone, Default)]
+pub struct OutputRoot(pub Option<PathBuf>);
+
+/// Resolve a path relative to the current directory
+pub fn absolute_path(path: impl AsRef<Path>) -> Result<PathBuf, BrpError> {
+    let path_buf = path.as_ref();
+    if path_buf.is_absolute() {
+        Ok(path_buf.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()
+            .map_err(|e| BrpError {
+                code:    error_codes::INTERNAL_ERROR,
+                message: format!("Failed to get current directory: {e}"),
+                data:    None,
+            })?
+            .join(path_buf))
+    }
+}
+
+/// Resolve a path that a request will write to or read a baseline from, confining it to the
+/// output root
+pub fn resolve_output_path(world: &World, path: &str) -> Result<PathBuf, BrpError> {
+    let Some(root) = world
+        .get_resource::<OutputRoot>()
+        .and_then(|root| root.0.as_ref())
+    else {
+        return absolute_path(path);
+    };
+
+    let invalid = |message: String| BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    };
+
+    let path_buf = Path::new(path);
+    if path_buf
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return Err(invalid(format!(
+            "Output path '{path}' must not contain '..'"
+        )));
+    }
+
+    let unresolved = if path_buf.is_absolute() {
+        path_buf.to_path_buf()
+    } else {
+        root.join(path_buf)
+    };
+    let resolve = |path: &Path| {
+        resolve_symlinks(path).map_err(|e| BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: format!("Failed to resolve output path {}: {e}", path.display()),
+            data:    None,
+        })
+    };
+    let resolved = resolve(&unresolved)?;
+    if resolved.starts_with(resolve(root)?) {
+        Ok(resolved)
+    } else {
+        Err(invalid(format!(
+            "Output path '{path}' is outside the output root {}",
+            root.display()
+        )))
+    }
+}
+
+/// Canonicalize the deepest existing ancestor of `path` and append the components below it
+///
+/// Dangling symlinks count as existing, so they fail to canonicalize instead of being written
+/// through.
+fn resolve_symlinks(path: &Path) -> std::io::Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+
+    let mut resolved = existing.canonicalize()?;
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
+}
+
+/// Whether a request may replace existing files, replacing them by default without an output root
+pub fn overwrite_allowed(world: &World, overwrite: Option<bool>) -> bool {
+    overwrite.unwrap_or_else(|| {
+        world
+            .get_resource::<OutputRoot>()
+            .is_none_or(|root| root.0.is_none())
+    })
+}
+
+/// Directory that files without a requested path are written to
+pub fn default_output_directory(world: &World) -> Result<PathBuf, BrpError> {
+    world
+        .get_resource::<OutputRoot>()
+        .and_then(|root| root.0.clone())
+        .map_or_else(|| absolute_path("."), Ok)
+}
+
+/// Unused path in `directory` named after the current time, such as `screenshot_1718000000000.png`
+pub fn timestamped_path(directory: &Path, prefix: &str, extension: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let mut path = directory.join(format!("{prefix}_{millis}.{extension}"));
+    let mut suffix = 1;
+    while path.exists() {
+        path = directory.join(format!("{prefix}_{millis}_{suffix}.{extension}"));
+        suffix += 1;
+    }
+    path
+}
+
+/// Refuse to write over an existing file unless overwriting was requested
+///
+/// This reports the conflict when the request is made. The write itself goes through
+/// [`write_file`], which refuses existing files atomically.
+pub fn check_overwrite(path: &Path, overwrite: bool) -> Result<(), BrpError> {
+    if path.exists() && !overwrite {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!(
+                "{} already exists. Pass 'overwrite': true to replace it",
+                path.display()
+            ),
+            data:    None,
+        });
+    }
+    Ok(())
+}
+
+/// Write `bytes` to `path`, failing if the file exists unless `overwrite` is set
+pub fn write_file(path: &Path, bytes: &[u8], overwrite: bool) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(path)?.write_all(bytes)
+}
+
+/// Create an empty directory under the temp directory that no other test uses
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+pub fn unique_temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let directory = std::env::temp_dir().join(format!(
+        "bevy_brp_extras_{name}_{}_{nanos}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&directory).expect("Expected temp directory to be created");
+    directory
+        .canonicalize()
+        .expect("Expected temp directory to resolve")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_output_paths_confined_to_root() {
+        let root = unique_temp_dir("output_root");
+        let mut world = World::new();
+        world.insert_resource(OutputRoot(Some(root.clone())));
+
+        let path = resolve_output_path(&world, "shots/menu.png").expect("Expected relative path");
+        assert_eq!(path, root.join("shots/menu.png"));
+        let inside = root.join("menu.png");
+        let path = resolve_output_path(&world, &inside.to_string_lossy())
+            .expect("Expected absolute path inside the root");
+        assert_eq!(path, inside);
+
+        assert!(resolve_output_path(&world, "../escape.png").is_err());
+        assert!(resolve_output_path(&world, "shots/../../escape.png").is_err());
+        let outside = std::env::temp_dir().join("escape.png");
+        assert!(resolve_output_path(&world, &outside.to_string_lossy()).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(std::env::temp_dir(), root.join("link"))
+                .expect("Expected symlink to be created");
+            assert!(resolve_output_path(&world, "link/escape.png").is_err());
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_existing_files_not_overwritten() {
+        let path = std::env::temp_dir();
+        assert!(check_overwrite(&path, false).is_err());
+        assert!(check_overwrite(&path, true).is_ok());
+        assert!(check_overwrite(&path.join("bevy_brp_extras_missing.png"), false).is_ok());
+
+        let directory = unique_temp_dir("overwrite");
+        let file = directory.join("shot.png");
+        write_file(&file, b"first", false).expect("Expected new file to be written");
+        assert!(write_file(&file, b"second", false).is_err());
+        write_file(&file, b"third", true).expect("Expected file to be replaced");
+        assert_eq!(std::fs::read(&file).expect("Expected file"), b"third");
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let mut world = World::new();
+        assert!(overwrite_allowed(&world, None));
+        world.insert_resource(OutputRoot(Some(directory)));
+        assert!(!overwrite_allowed(&world, None));
+        assert!(overwrite_allowed(&world, Some(true)));
+    }
+}
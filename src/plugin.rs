@@ -1,28 +1,65 @@
 //! Plugin implementation for extra BRP methods
 
+use std::path::PathBuf;
+
 use bevy::prelude::*;
-use bevy::remote::RemotePlugin;
 use bevy::remote::http::RemoteHttpPlugin;
+use bevy::remote::{RemoteLast, RemotePlugin, RemoteSet};
+use bevy::render::camera::CameraUpdateSystem;
+use bevy::render::renderer::RenderDevice;
 
-use crate::{DEFAULT_REMOTE_PORT, debug_mode, discovery, keyboard, screenshot, shutdown};
+use crate::{
+    DEFAULT_REMOTE_PORT, compare, debug_mode, deferred, discovery, events, frame_capture, headless,
+    hierarchy, keyboard, output, recording, screenshot, selector, shutdown, states, time_control,
+    touch, ui_click, ui_tree, wait_for,
+};
 
 /// Command prefix for `brp_extras` methods
 const EXTRAS_COMMAND_PREFIX: &str = "brp_extras/";
 
+/// Methods that respond after one or more frames, which need an ID per request
+const DEFERRED_METHODS: [&str; 3] = ["screenshot", "compare_screenshot", "wait_for"];
+
 /// Plugin that adds extra BRP methods to a Bevy app
 ///
 /// Currently provides:
-/// - `brp_extras/screenshot`: Capture screenshots
-/// - `brp_extras/shutdown`: Gracefully shutdown the app
+/// - `brp_extras/screenshot`: Capture screenshots, optionally returned inline as base64
+/// - `brp_extras/screenshot_status`: Report the progress of a screenshot by request ID
+/// - `brp_extras/compare_screenshot`: Compare a screenshot with a baseline image
+/// - `brp_extras/capture_frames`: Capture a sequence of frames to numbered images
+/// - `brp_extras/shutdown`: Gracefully shutdown the app with an optional exit code and delay
+/// - `brp_extras/cancel_shutdown`: Cancel a pending shutdown
+/// - `brp_extras/pause`: Pause virtual time while frames and BRP requests keep running
+/// - `brp_extras/resume`: Resume virtual time
+/// - `brp_extras/step`: Advance a number of frames or fixed timestep ticks, then pause
+/// - `brp_extras/time_settings`: Read and set virtual time speed and the fixed timestep
+/// - `brp_extras/wait_for`: Respond once a condition holds in the app, or fail after a timeout
+/// - `brp_extras/list_states`: List registered states with their current values
+/// - `brp_extras/set_state`: Request a state transition by writing `NextState<S>`
+/// - `brp_extras/send_event`: Send a reflected app event built from JSON
+/// - `brp_extras/hierarchy`: Dump the entity hierarchy with names and component summaries
+/// - `brp_extras/find_entities`: Find entities by `Name`, `Name` path, glob and components
+/// - `brp_extras/click_ui`: Click a `bevy_ui` node by entity or `Name`
+/// - `brp_extras/ui_tree`: Dump the `bevy_ui` layout tree with layout, visibility and text
 /// - `brp_extras/discover_format`: Discover component format information
 /// - `brp_extras/send_keys`: Send keyboard input
+/// - `brp_extras/input_state`: Report held keys and button input state
+/// - `brp_extras/release_all`: Release every injected key and touch and stop input replays
+/// - `brp_extras/send_touch`: Send touch input
+/// - `brp_extras/send_gesture`: Send a tap, long press, swipe or pinch gesture
+/// - `brp_extras/start_input_recording`: Start recording keyboard, mouse and gamepad input
+/// - `brp_extras/stop_input_recording`: Stop recording and return the input script
+/// - `brp_extras/replay_input`: Replay a recorded input script
+/// - `brp_extras/stop_input_replay`: Cancel an input replay in progress
 /// - `brp_extras/set_debug_mode`: Enable/disable debug information in responses
 #[allow(non_upper_case_globals)]
 pub const BrpExtrasPlugin: BrpExtrasPlugin = BrpExtrasPlugin::new();
 
 /// Plugin type for adding extra BRP methods
 pub struct BrpExtrasPlugin {
-    port: Option<u16>,
+    port:          Option<u16>,
+    output_root:   Option<PathBuf>,
+    headless_size: Option<UVec2>,
 }
 
 impl Default for BrpExtrasPlugin {
@@ -35,13 +72,44 @@ impl BrpExtrasPlugin {
     /// Create a new plugin instance with default port
     #[must_use]
     pub const fn new() -> Self {
-        Self { port: None }
+        Self {
+            port:          None,
+            output_root:   None,
+            headless_size: None,
+        }
     }
 
     /// Create plugin with custom port
     #[must_use]
     pub const fn with_port(port: u16) -> Self {
-        Self { port: Some(port) }
+        Self {
+            port:          Some(port),
+            output_root:   None,
+            headless_size: None,
+        }
+    }
+
+    /// Confine files written by BRP methods, such as screenshots, to a directory
+    ///
+    /// Relative paths in requests resolve against this directory, and paths outside it are
+    /// rejected. A relative root resolves against the current directory.
+    #[must_use]
+    pub fn with_output_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.output_root = Some(root.into());
+        self
+    }
+
+    /// Render to an offscreen image of the given size when the app has no window
+    ///
+    /// Cameras that target the primary window render to this image while no primary window
+    /// exists, so screenshot, compare and frame capture methods work in windowless apps. An app
+    /// without a camera gets a 2D camera rendering to the image until it spawns its own.
+    /// Combine with [`fallback_render_plugin`](crate::fallback_render_plugin) to render with a
+    /// software adapter.
+    #[must_use]
+    pub const fn with_headless(mut self, width: u32, height: u32) -> Self {
+        self.headless_size = Some(UVec2::new(width, height));
+        self
     }
 
     /// Get the effective port, checking environment variable first
@@ -84,51 +152,241 @@ impl Plugin for BrpExtrasPlugin {
             EXTRAS_COMMAND_PREFIX
         );
 
-        let remote_plugin = RemotePlugin::default()
-            .with_method(
-                format!("{EXTRAS_COMMAND_PREFIX}screenshot"),
-                screenshot::handler,
-            )
-            .with_method(
-                format!("{EXTRAS_COMMAND_PREFIX}shutdown"),
-                shutdown::handler,
-            )
-            .with_method(
-                format!("{EXTRAS_COMMAND_PREFIX}discover_format"),
-                discovery::handler,
-            )
-            .with_method(
-                format!("{EXTRAS_COMMAND_PREFIX}send_keys"),
-                keyboard::send_keys_handler,
-            )
-            .with_method(
-                format!("{EXTRAS_COMMAND_PREFIX}set_debug_mode"),
-                debug_mode::handler,
-            );
+        let remote_plugin = remote_plugin();
 
         let http_plugin = RemoteHttpPlugin::default().with_port(effective_port);
 
+        // Resolve the output root once so later changes to the current directory don't move it
+        let output_root = self
+            .output_root
+            .as_ref()
+            .map(|root| output::absolute_path(root).unwrap_or_else(|_| root.clone()));
+        app.insert_resource(output::OutputRoot(output_root));
+
         app.add_plugins((remote_plugin, http_plugin));
 
         // Add the system to process timed key releases
         app.add_systems(Update, keyboard::process_timed_key_releases);
 
-        // Add the system to handle deferred shutdown
+        // Add the system to send scheduled touch events
+        app.add_systems(Update, touch::process_timed_touch_events);
+
+        // Add the system to release mouse buttons pressed by UI clicks
+        app.add_systems(Update, ui_click::process_click_releases);
+
+        // Add the systems to record input and replay recorded input
+        app.add_systems(First, recording::process_input_replay);
+        app.add_systems(PreUpdate, recording::record_input_events);
+        app.add_systems(Last, recording::despawn_finished_replay_gamepads);
+
+        // Add the systems that give each deferred request an ID and drop requests that are no
+        // longer waiting for a reply
+        app.insert_resource(deferred::DeferredMailbox::new(
+            DEFERRED_METHODS.map(|method| format!("{EXTRAS_COMMAND_PREFIX}{method}")),
+        ));
+        app.add_systems(Startup, deferred::setup_mailbox);
+        app.add_systems(
+            RemoteLast,
+            deferred::forward_requests.before(RemoteSet::ProcessRequests),
+        );
+        app.add_systems(
+            First,
+            (
+                deferred::expire_idle_requests::<screenshot::PendingScreenshot>,
+                deferred::expire_idle_requests::<compare::PendingComparison>,
+                deferred::expire_idle_requests::<wait_for::PendingWait>,
+            ),
+        );
+
+        // Add the system to capture frame sequences
+        app.add_systems(Last, frame_capture::process_frame_capture);
+
+        // Add the systems to render to an offscreen image in headless mode
+        if let Some(size) = self.headless_size {
+            app.insert_resource(headless::HeadlessConfig { size });
+            app.add_systems(Startup, headless::setup_headless_target);
+            app.add_systems(
+                PostUpdate,
+                (headless::ensure_headless_camera, headless::retarget_cameras)
+                    .before(CameraUpdateSystem),
+            );
+        }
+
+        // Add the systems to step frames and fixed ticks while paused
+        app.add_systems(Last, time_control::process_frame_step);
+        app.add_systems(
+            RunFixedMainLoop,
+            time_control::process_fixed_step.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+        );
+
+        // Add the system to handle deferred shutdown, waiting for app shutdown hooks
+        app.add_event::<shutdown::BrpShutdownRequested>();
+        app.init_resource::<shutdown::ShutdownHooks>();
         app.add_systems(Update, shutdown::deferred_shutdown_system);
 
         app.add_systems(Startup, move |_world: &mut World| {
             log_initialization(effective_port, &source_description);
         });
     }
+
+    fn cleanup(&self, app: &mut App) {
+        // The render device exists once every plugin has finished, unless there is no renderer
+        if !app.world().contains_resource::<RenderDevice>() {
+            app.init_resource::<headless::RendererUnavailable>();
+        }
+    }
+}
+
+/// Remote plugin with every `brp_extras` method registered
+#[allow(clippy::too_many_lines)]
+fn remote_plugin() -> RemotePlugin {
+    RemotePlugin::default()
+        .with_watching_method(
+            format!("{EXTRAS_COMMAND_PREFIX}screenshot"),
+            screenshot::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}screenshot_status"),
+            screenshot::status_handler,
+        )
+        .with_watching_method(
+            format!("{EXTRAS_COMMAND_PREFIX}compare_screenshot"),
+            compare::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}capture_frames"),
+            frame_capture::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}shutdown"),
+            shutdown::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}cancel_shutdown"),
+            shutdown::cancel_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}pause"),
+            time_control::pause_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}resume"),
+            time_control::resume_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}step"),
+            time_control::step_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}time_settings"),
+            time_control::time_settings_handler,
+        )
+        .with_watching_method(
+            format!("{EXTRAS_COMMAND_PREFIX}wait_for"),
+            wait_for::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}list_states"),
+            states::list_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}set_state"),
+            states::set_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}send_event"),
+            events::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}hierarchy"),
+            hierarchy::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}find_entities"),
+            selector::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}click_ui"),
+            ui_click::handler,
+        )
+        .with_method(format!("{EXTRAS_COMMAND_PREFIX}ui_tree"), ui_tree::handler)
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}discover_format"),
+            discovery::handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}send_keys"),
+            keyboard::send_keys_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}input_state"),
+            keyboard::input_state_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}release_all"),
+            keyboard::release_all_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}send_touch"),
+            touch::send_touch_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}send_gesture"),
+            touch::send_gesture_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}start_input_recording"),
+            recording::start_recording_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}stop_input_recording"),
+            recording::stop_recording_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}replay_input"),
+            recording::replay_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}stop_input_replay"),
+            recording::stop_replay_handler,
+        )
+        .with_method(
+            format!("{EXTRAS_COMMAND_PREFIX}set_debug_mode"),
+            debug_mode::handler,
+        )
 }
 
 fn log_initialization(port: u16, source_description: &str) {
     info!("BRP extras enabled on http://localhost:{port} ({source_description})");
     trace!("Additional BRP methods available:");
     trace!("  - brp_extras/screenshot - Take a screenshot");
+    trace!("  - brp_extras/screenshot_status - Report screenshot progress");
+    trace!("  - brp_extras/compare_screenshot - Compare a screenshot with a baseline image");
+    trace!("  - brp_extras/capture_frames - Capture a sequence of frames");
     trace!("  - brp_extras/shutdown - Shutdown the app");
+    trace!("  - brp_extras/cancel_shutdown - Cancel a pending shutdown");
+    trace!("  - brp_extras/pause - Pause virtual time");
+    trace!("  - brp_extras/resume - Resume virtual time");
+    trace!("  - brp_extras/step - Advance frames or fixed ticks, then pause");
+    trace!("  - brp_extras/time_settings - Read and set time speed and fixed timestep");
+    trace!("  - brp_extras/wait_for - Wait for a condition in the app");
+    trace!("  - brp_extras/list_states - List registered states");
+    trace!("  - brp_extras/set_state - Request a state transition");
+    trace!("  - brp_extras/send_event - Send a reflected event");
+    trace!("  - brp_extras/hierarchy - Dump the entity hierarchy");
+    trace!("  - brp_extras/find_entities - Find entities by name path and components");
+    trace!("  - brp_extras/click_ui - Click a UI node");
+    trace!("  - brp_extras/ui_tree - Dump the UI layout tree");
     trace!("  - brp_extras/discover_format - Discover component format information");
     trace!("  - brp_extras/send_keys - Send keyboard input");
+    trace!("  - brp_extras/input_state - Report held keys and button input state");
+    trace!("  - brp_extras/release_all - Release injected keys and touches and stop input replays");
+    trace!("  - brp_extras/send_touch - Send touch input");
+    trace!("  - brp_extras/send_gesture - Send a touch gesture");
+    trace!("  - brp_extras/start_input_recording - Start recording input");
+    trace!("  - brp_extras/stop_input_recording - Stop recording and return the input script");
+    trace!("  - brp_extras/replay_input - Replay a recorded input script");
+    trace!("  - brp_extras/stop_input_replay - Cancel an input replay");
     trace!("  - brp_extras/set_debug_mode - Enable/disable debug information");
 }
 
@@ -0,0 +1,795 @@
+//! Input recording and replay for BRP extras
+//!
+//! Recording captures keyboard, mouse and gamepad events as they arrive, tagged with the frame
+//! and time they were seen. The resulting script can be replayed later through the same
+//! `World::send_event` injection path that `send_keys` uses.
+//!
+//! Events injected by BRP methods such as `send_keys` and `click_ui` are sent with
+//! [`send_injected`] or passed to [`ActiveInputRecording::skip`], and are left out of recordings.
+//! Touch input is not recorded.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use bevy::ecs::event::EventId;
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonState;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent, RawGamepadEvent};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Maximum number of events kept in a single recording
+const MAX_RECORDED_EVENTS: usize = 100_000;
+
+/// Name given to gamepads connected for replay
+const REPLAY_GAMEPAD_NAME: &str = "BRP Replay Gamepad";
+
+/// A single input event captured during recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "event", rename_all = "snake_case")]
+pub enum RecordedInput {
+    /// A keyboard key was pressed or released
+    Keyboard(KeyboardInput),
+    /// A mouse button was pressed or released
+    MouseButton(MouseButtonInput),
+    /// The cursor moved within a window
+    CursorMoved(CursorMoved),
+    /// Raw mouse motion
+    MouseMotion(MouseMotion),
+    /// The mouse wheel was scrolled
+    MouseWheel(MouseWheel),
+    /// A raw gamepad connection, button or axis event
+    Gamepad(RawGamepadEvent),
+}
+
+/// A recorded input event with the frame and time it occurred
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedInputEvent {
+    /// Frames since the recording started
+    pub frame:        u32,
+    /// Milliseconds since the recording started
+    pub timestamp_ms: u64,
+    /// The captured event
+    pub input:        RecordedInput,
+}
+
+/// A serializable input script produced by `stop_input_recording`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputScript {
+    /// Number of frames covered by the recording
+    pub frame_count: u32,
+    /// Length of the recording in milliseconds
+    pub duration_ms: u64,
+    /// Recorded events in the order they occurred
+    pub events:      Vec<RecordedInputEvent>,
+}
+
+/// How replayed events are scheduled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayTiming {
+    /// Emit each event on the same frame offset it was recorded on
+    #[default]
+    Frames,
+    /// Emit each event once the same amount of real time has passed
+    Timestamps,
+}
+
+/// Request structure for `replay_input`
+#[derive(Debug, Deserialize)]
+pub struct ReplayInputRequest {
+    /// The script to replay
+    pub script: InputScript,
+    /// How replayed events are scheduled
+    #[serde(default)]
+    pub timing: ReplayTiming,
+}
+
+/// Resource present while input is being recorded
+#[derive(Resource, Default)]
+pub struct ActiveInputRecording {
+    frame:    u32,
+    elapsed:  Duration,
+    events:   Vec<RecordedInputEvent>,
+    dropped:  usize,
+    injected: InjectedInput,
+}
+
+impl ActiveInputRecording {
+    /// Leave an event injected by a BRP method out of the recording
+    pub fn skip<E: InjectedEvent>(&mut self, id: EventId<E>) {
+        E::injected_ids(&mut self.injected).push(id);
+    }
+}
+
+/// IDs of events injected since the recorder last ran
+#[derive(Default)]
+pub struct InjectedInput {
+    keyboard:     Vec<EventId<KeyboardInput>>,
+    mouse_button: Vec<EventId<MouseButtonInput>>,
+    cursor_moved: Vec<EventId<CursorMoved>>,
+}
+
+impl InjectedInput {
+    fn clear(&mut self) {
+        self.keyboard.clear();
+        self.mouse_button.clear();
+        self.cursor_moved.clear();
+    }
+}
+
+/// Recordable input events that BRP methods inject
+pub trait InjectedEvent: Event + Sized {
+    /// The list of injected IDs for this event type
+    fn injected_ids(injected: &mut InjectedInput) -> &mut Vec<EventId<Self>>;
+}
+
+impl InjectedEvent for KeyboardInput {
+    fn injected_ids(injected: &mut InjectedInput) -> &mut Vec<EventId<Self>> {
+        &mut injected.keyboard
+    }
+}
+
+impl InjectedEvent for MouseButtonInput {
+    fn injected_ids(injected: &mut InjectedInput) -> &mut Vec<EventId<Self>> {
+        &mut injected.mouse_button
+    }
+}
+
+impl InjectedEvent for CursorMoved {
+    fn injected_ids(injected: &mut InjectedInput) -> &mut Vec<EventId<Self>> {
+        &mut injected.cursor_moved
+    }
+}
+
+/// Send an input event on behalf of a BRP method, leaving it out of any active recording
+pub fn send_injected<E: InjectedEvent>(world: &mut World, event: E) {
+    if let Some(id) = world.send_event(event)
+        && let Some(mut recording) = world.get_resource_mut::<ActiveInputRecording>()
+    {
+        recording.skip(id);
+    }
+}
+
+/// Resource present while an input script is being replayed
+#[derive(Resource)]
+pub struct ActiveInputReplay {
+    events:       VecDeque<RecordedInputEvent>,
+    timing:       ReplayTiming,
+    frame:        u32,
+    elapsed:      Duration,
+    gamepads:     HashMap<Entity, Entity>,
+    held_keys:    Vec<KeyboardInput>,
+    held_buttons: Vec<MouseButtonInput>,
+}
+
+/// Marker for virtual gamepads whose replay finished, despawned once their disconnection is
+/// processed
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct FinishedReplayGamepad;
+
+/// Event readers for every input event type that can be recorded
+#[derive(SystemParam)]
+pub struct RecordableInputEvents<'w, 's> {
+    keyboard:     EventReader<'w, 's, KeyboardInput>,
+    mouse_button: EventReader<'w, 's, MouseButtonInput>,
+    cursor_moved: EventReader<'w, 's, CursorMoved>,
+    mouse_motion: EventReader<'w, 's, MouseMotion>,
+    mouse_wheel:  EventReader<'w, 's, MouseWheel>,
+    gamepad:      EventReader<'w, 's, RawGamepadEvent>,
+}
+
+impl RecordableInputEvents<'_, '_> {
+    /// Drain all pending events into a list of recorded inputs, skipping injected ones
+    fn drain(&mut self, injected: &InjectedInput) -> Vec<RecordedInput> {
+        let mut inputs = Vec::new();
+        inputs.extend(
+            self.keyboard
+                .read_with_id()
+                .filter(|(_, id)| !injected.keyboard.contains(id))
+                .map(|(event, _)| RecordedInput::Keyboard(event.clone())),
+        );
+        inputs.extend(
+            self.mouse_button
+                .read_with_id()
+                .filter(|(_, id)| !injected.mouse_button.contains(id))
+                .map(|(event, _)| RecordedInput::MouseButton(*event)),
+        );
+        inputs.extend(
+            self.cursor_moved
+                .read_with_id()
+                .filter(|(_, id)| !injected.cursor_moved.contains(id))
+                .map(|(event, _)| RecordedInput::CursorMoved(event.clone())),
+        );
+        inputs.extend(
+            self.mouse_motion
+                .read()
+                .copied()
+                .map(RecordedInput::MouseMotion),
+        );
+        inputs.extend(
+            self.mouse_wheel
+                .read()
+                .copied()
+                .map(RecordedInput::MouseWheel),
+        );
+        inputs.extend(self.gamepad.read().cloned().map(RecordedInput::Gamepad));
+        inputs
+    }
+
+    /// Discard all pending events
+    fn clear(&mut self) {
+        self.keyboard.clear();
+        self.mouse_button.clear();
+        self.cursor_moved.clear();
+        self.mouse_motion.clear();
+        self.mouse_wheel.clear();
+        self.gamepad.clear();
+    }
+}
+
+/// Handler for `start_input_recording` requests
+///
+/// Begins capturing keyboard, mouse and gamepad events
+///
+/// # Errors
+///
+/// Returns `BrpError` if a recording is already in progress
+pub fn start_recording_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    if world.contains_resource::<ActiveInputRecording>() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_REQUEST,
+            message: "Input recording is already in progress".to_string(),
+            data:    None,
+        });
+    }
+
+    world.init_resource::<ActiveInputRecording>();
+    info!("Input recording started");
+
+    Ok(json!({
+        "success": true,
+        "message": "Input recording started"
+    }))
+}
+
+/// Handler for `stop_input_recording` requests
+///
+/// Stops capturing input and returns the recorded script
+///
+/// # Errors
+///
+/// Returns `BrpError` if no recording is in progress
+pub fn stop_recording_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let recording = world
+        .remove_resource::<ActiveInputRecording>()
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_REQUEST,
+            message: "No input recording is in progress".to_string(),
+            data:    None,
+        })?;
+
+    if recording.dropped > 0 {
+        warn!(
+            "Input recording exceeded {MAX_RECORDED_EVENTS} events, {} events were dropped",
+            recording.dropped
+        );
+    }
+
+    let script = InputScript {
+        frame_count: recording.frame,
+        duration_ms: u64::try_from(recording.elapsed.as_millis()).unwrap_or(u64::MAX),
+        events:      recording.events,
+    };
+
+    info!(
+        "Input recording stopped - {} events over {} frames",
+        script.events.len(),
+        script.frame_count
+    );
+
+    Ok(json!({
+        "success": true,
+        "event_count": script.events.len(),
+        "dropped_events": recording.dropped,
+        "script": script
+    }))
+}
+
+/// Handler for `replay_input` requests
+///
+/// Replays a script produced by `stop_input_recording`. Gamepads referenced by the script are
+/// connected as virtual gamepads for the duration of the replay.
+///
+/// # Errors
+///
+/// Returns `BrpError` if:
+/// - Request parameters are missing or invalid
+/// - A replay is already in progress
+pub fn replay_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: ReplayInputRequest = if let Some(params) = params {
+        serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid request format: {e}"),
+            data:    None,
+        })?
+    } else {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Missing request parameters".to_string(),
+            data:    None,
+        });
+    };
+
+    if world.contains_resource::<ActiveInputReplay>() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_REQUEST,
+            message: "An input replay is already in progress".to_string(),
+            data:    None,
+        });
+    }
+
+    let mut events = request.script.events;
+    match request.timing {
+        ReplayTiming::Frames => events.sort_by_key(|event| event.frame),
+        ReplayTiming::Timestamps => events.sort_by_key(|event| event.timestamp_ms),
+    }
+
+    // Connect a virtual gamepad for every gamepad the script refers to
+    let mut gamepads = HashMap::new();
+    for event in &events {
+        if let RecordedInput::Gamepad(gamepad_event) = &event.input {
+            let recorded = recorded_gamepad(gamepad_event);
+            if let Entry::Vacant(entry) = gamepads.entry(recorded) {
+                let virtual_gamepad = world.spawn_empty().id();
+                send_gamepad_connection(
+                    world,
+                    virtual_gamepad,
+                    GamepadConnection::Connected {
+                        name:       REPLAY_GAMEPAD_NAME.to_string(),
+                        vendor_id:  None,
+                        product_id: None,
+                    },
+                );
+                entry.insert(virtual_gamepad);
+            }
+        }
+    }
+
+    let event_count = events.len();
+    let gamepad_count = gamepads.len();
+    info!(
+        "Input replay started - {event_count} events, timing: {:?}",
+        request.timing
+    );
+
+    world.insert_resource(ActiveInputReplay {
+        events: events.into(),
+        timing: request.timing,
+        frame: 0,
+        elapsed: Duration::ZERO,
+        gamepads,
+        held_keys: Vec::new(),
+        held_buttons: Vec::new(),
+    });
+
+    Ok(json!({
+        "success": true,
+        "event_count": event_count,
+        "frame_count": request.script.frame_count,
+        "duration_ms": request.script.duration_ms,
+        "virtual_gamepads": gamepad_count,
+        "timing": request.timing
+    }))
+}
+
+/// Handler for `stop_input_replay` requests
+///
+/// Cancels the active replay and releases any keys or mouse buttons it left pressed
+///
+/// # Errors
+///
+/// Returns `BrpError` if no replay is in progress
+pub fn stop_replay_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let events_remaining = cancel_replay(world).ok_or_else(|| BrpError {
+        code:    error_codes::INVALID_REQUEST,
+        message: "No input replay is in progress".to_string(),
+        data:    None,
+    })?;
+
+    Ok(json!({
+        "success": true,
+        "events_remaining": events_remaining
+    }))
+}
+
+/// Cancel the active replay, releasing what it left pressed
+///
+/// Returns the number of events that were not replayed, or `None` if no replay was in progress
+pub fn cancel_replay(world: &mut World) -> Option<usize> {
+    let replay = world.remove_resource::<ActiveInputReplay>()?;
+    let events_remaining = replay.events.len();
+    finish_replay(world, replay);
+
+    info!("Input replay stopped with {events_remaining} events remaining");
+    Some(events_remaining)
+}
+
+/// System that captures input events while a recording is active
+#[allow(clippy::needless_pass_by_value)]
+pub fn record_input_events(
+    recording: Option<ResMut<ActiveInputRecording>>,
+    replay: Option<Res<ActiveInputReplay>>,
+    time: Res<Time<Real>>,
+    mut input_events: RecordableInputEvents,
+) {
+    // Replayed events are not real input, so they are never recorded
+    let Some(mut recording) = recording.filter(|_| replay.is_none()) else {
+        input_events.clear();
+        return;
+    };
+
+    let timestamp_ms = u64::try_from(recording.elapsed.as_millis()).unwrap_or(u64::MAX);
+    let frame = recording.frame;
+
+    let inputs = input_events.drain(&recording.injected);
+    recording.injected.clear();
+    for input in inputs {
+        if recording.events.len() >= MAX_RECORDED_EVENTS {
+            recording.dropped += 1;
+            continue;
+        }
+        recording.events.push(RecordedInputEvent {
+            frame,
+            timestamp_ms,
+            input,
+        });
+    }
+
+    recording.frame = recording.frame.saturating_add(1);
+    recording.elapsed += time.delta();
+}
+
+/// System that emits replayed input events when they are due
+pub fn process_input_replay(world: &mut World) {
+    let Some(mut replay) = world.remove_resource::<ActiveInputReplay>() else {
+        return;
+    };
+
+    let window = world
+        .query_filtered::<Entity, With<PrimaryWindow>>()
+        .iter(world)
+        .next()
+        .unwrap_or(Entity::PLACEHOLDER);
+
+    while let Some(event) = replay.events.front() {
+        let due = match replay.timing {
+            ReplayTiming::Frames => event.frame <= replay.frame,
+            ReplayTiming::Timestamps => {
+                u128::from(event.timestamp_ms) <= replay.elapsed.as_millis()
+            }
+        };
+        if !due {
+            break;
+        }
+        if let Some(event) = replay.events.pop_front() {
+            send_replayed_input(world, &mut replay, event.input, window);
+        }
+    }
+
+    if replay.events.is_empty() {
+        info!("Input replay complete");
+        finish_replay(world, replay);
+        return;
+    }
+
+    replay.frame = replay.frame.saturating_add(1);
+    replay.elapsed += world
+        .get_resource::<Time<Real>>()
+        .map_or(Duration::ZERO, Time::delta);
+    world.insert_resource(replay);
+}
+
+/// Send a single replayed event, retargeting windows and gamepads to the running app
+fn send_replayed_input(
+    world: &mut World,
+    replay: &mut ActiveInputReplay,
+    input: RecordedInput,
+    window: Entity,
+) {
+    match input {
+        RecordedInput::Keyboard(mut event) => {
+            event.window = window;
+            replay
+                .held_keys
+                .retain(|held| held.key_code != event.key_code);
+            if event.state == ButtonState::Pressed {
+                replay.held_keys.push(event.clone());
+            }
+            world.send_event(event);
+        }
+        RecordedInput::MouseButton(mut event) => {
+            event.window = window;
+            replay
+                .held_buttons
+                .retain(|held| held.button != event.button);
+            if event.state == ButtonState::Pressed {
+                replay.held_buttons.push(event);
+            }
+            world.send_event(event);
+        }
+        RecordedInput::CursorMoved(mut event) => {
+            event.window = window;
+            world.send_event(event);
+        }
+        RecordedInput::MouseMotion(event) => {
+            world.send_event(event);
+        }
+        RecordedInput::MouseWheel(mut event) => {
+            event.window = window;
+            world.send_event(event);
+        }
+        RecordedInput::Gamepad(mut event) => {
+            let Some(&gamepad) = replay.gamepads.get(&recorded_gamepad(&event)) else {
+                return;
+            };
+            match &mut event {
+                // Virtual gamepads are connected for the whole replay, so recorded connection
+                // changes would connect them twice or disconnect them early
+                RawGamepadEvent::Connection(_) => return,
+                RawGamepadEvent::Button(button) => button.gamepad = gamepad,
+                RawGamepadEvent::Axis(axis) => axis.gamepad = gamepad,
+            }
+            world.send_event(event);
+        }
+    }
+}
+
+/// Release anything the replay left pressed and disconnect its virtual gamepads
+///
+/// The gamepad entities are despawned by [`despawn_finished_replay_gamepads`] after Bevy handled
+/// the disconnection.
+fn finish_replay(world: &mut World, replay: ActiveInputReplay) {
+    for mut event in replay.held_keys {
+        event.state = ButtonState::Released;
+        event.repeat = false;
+        world.send_event(event);
+    }
+    for mut event in replay.held_buttons {
+        event.state = ButtonState::Released;
+        world.send_event(event);
+    }
+    for gamepad in replay.gamepads.into_values() {
+        send_gamepad_connection(world, gamepad, GamepadConnection::Disconnected);
+        if let Ok(mut entity) = world.get_entity_mut(gamepad) {
+            entity.insert(FinishedReplayGamepad);
+        }
+    }
+}
+
+/// System that despawns virtual gamepads once Bevy removed their `Gamepad` on disconnection
+pub fn despawn_finished_replay_gamepads(
+    mut commands: Commands,
+    gamepads: Query<Entity, (With<FinishedReplayGamepad>, Without<Gamepad>)>,
+) {
+    for gamepad in &gamepads {
+        commands.entity(gamepad).despawn();
+    }
+}
+
+/// Send a gamepad connection change through both the raw and processed event channels
+fn send_gamepad_connection(world: &mut World, gamepad: Entity, connection: GamepadConnection) {
+    let event = GamepadConnectionEvent::new(gamepad, connection);
+    world.send_event(RawGamepadEvent::Connection(event.clone()));
+    world.send_event(event);
+}
+
+/// Get the gamepad entity a recorded gamepad event refers to
+const fn recorded_gamepad(event: &RawGamepadEvent) -> Entity {
+    match event {
+        RawGamepadEvent::Connection(connection) => connection.gamepad,
+        RawGamepadEvent::Button(button) => button.gamepad,
+        RawGamepadEvent::Axis(axis) => axis.gamepad,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::InputPlugin;
+    use bevy::input::keyboard::{Key, NativeKey};
+
+    use super::*;
+
+    fn key_event(key_code: KeyCode, state: ButtonState) -> KeyboardInput {
+        KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        }
+    }
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin))
+            .add_event::<CursorMoved>()
+            .add_systems(PreUpdate, record_input_events)
+            .add_systems(First, process_input_replay)
+            .add_systems(Last, despawn_finished_replay_gamepads);
+        app
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_record_and_stop_returns_script() {
+        let mut app = test_app();
+
+        assert!(start_recording_handler(In(None), app.world_mut()).is_ok());
+        app.world_mut()
+            .send_event(key_event(KeyCode::KeyA, ButtonState::Pressed));
+        app.update();
+        app.world_mut()
+            .send_event(key_event(KeyCode::KeyA, ButtonState::Released));
+        app.update();
+
+        let response =
+            stop_recording_handler(In(None), app.world_mut()).expect("Expected a recorded script");
+        let script: InputScript =
+            serde_json::from_value(response["script"].clone()).expect("Invalid script");
+
+        assert_eq!(script.frame_count, 2);
+        assert_eq!(script.events.len(), 2);
+        assert_eq!(script.events[0].frame, 0);
+        assert_eq!(script.events[1].frame, 1);
+        assert!(matches!(
+            &script.events[1].input,
+            RecordedInput::Keyboard(event) if event.state == ButtonState::Released
+        ));
+    }
+
+    #[test]
+    fn test_recording_state_errors() {
+        let mut app = test_app();
+
+        assert!(stop_recording_handler(In(None), app.world_mut()).is_err());
+        assert!(start_recording_handler(In(None), app.world_mut()).is_ok());
+        assert!(start_recording_handler(In(None), app.world_mut()).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_replay_presses_keys_on_recorded_frames() {
+        let mut app = test_app();
+
+        let script = InputScript {
+            frame_count: 3,
+            duration_ms: 50,
+            events:      vec![
+                RecordedInputEvent {
+                    frame:        0,
+                    timestamp_ms: 0,
+                    input:        RecordedInput::Keyboard(key_event(
+                        KeyCode::Space,
+                        ButtonState::Pressed,
+                    )),
+                },
+                RecordedInputEvent {
+                    frame:        2,
+                    timestamp_ms: 32,
+                    input:        RecordedInput::Keyboard(key_event(
+                        KeyCode::Space,
+                        ButtonState::Released,
+                    )),
+                },
+            ],
+        };
+
+        let params = json!({ "script": script });
+        assert!(replay_handler(In(Some(params)), app.world_mut()).is_ok());
+
+        app.update();
+        let input = app.world().resource::<ButtonInput<KeyCode>>();
+        assert!(input.pressed(KeyCode::Space));
+
+        app.update();
+        app.update();
+        let input = app.world().resource::<ButtonInput<KeyCode>>();
+        assert!(!input.pressed(KeyCode::Space));
+        assert!(!app.world().contains_resource::<ActiveInputReplay>());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_injected_input_not_recorded() {
+        let mut app = test_app();
+
+        assert!(start_recording_handler(In(None), app.world_mut()).is_ok());
+        send_injected(
+            app.world_mut(),
+            key_event(KeyCode::KeyA, ButtonState::Pressed),
+        );
+        app.world_mut()
+            .send_event(key_event(KeyCode::KeyB, ButtonState::Pressed));
+        app.update();
+
+        let response =
+            stop_recording_handler(In(None), app.world_mut()).expect("Expected a recorded script");
+        let script: InputScript =
+            serde_json::from_value(response["script"].clone()).expect("Invalid script");
+
+        assert_eq!(script.events.len(), 1);
+        assert!(matches!(
+            &script.events[0].input,
+            RecordedInput::Keyboard(event) if event.key_code == KeyCode::KeyB
+        ));
+    }
+
+    #[test]
+    fn test_replay_skips_recorded_gamepad_connections() {
+        let mut app = test_app();
+
+        let recorded = Entity::from_raw(1000);
+        let at = |frame, event| RecordedInputEvent {
+            frame,
+            timestamp_ms: u64::from(frame) * 16,
+            input: RecordedInput::Gamepad(event),
+        };
+        let script = InputScript {
+            frame_count: 4,
+            duration_ms: 64,
+            events:      vec![
+                at(
+                    0,
+                    RawGamepadEvent::Connection(GamepadConnectionEvent::new(
+                        recorded,
+                        GamepadConnection::Connected {
+                            name:       "Recorded".to_string(),
+                            vendor_id:  None,
+                            product_id: None,
+                        },
+                    )),
+                ),
+                at(
+                    1,
+                    RawGamepadEvent::Connection(GamepadConnectionEvent::new(
+                        recorded,
+                        GamepadConnection::Disconnected,
+                    )),
+                ),
+                at(
+                    3,
+                    RawGamepadEvent::Connection(GamepadConnectionEvent::new(
+                        recorded,
+                        GamepadConnection::Disconnected,
+                    )),
+                ),
+            ],
+        };
+
+        let params = json!({ "script": script });
+        assert!(replay_handler(In(Some(params)), app.world_mut()).is_ok());
+
+        let mut virtual_gamepads = Vec::new();
+        for _ in 0..3 {
+            app.update();
+            let mut gamepads = app.world_mut().query_filtered::<Entity, With<Gamepad>>();
+            virtual_gamepads = gamepads.iter(app.world()).collect();
+            assert_eq!(virtual_gamepads.len(), 1);
+        }
+
+        // The virtual gamepad is disconnected and then despawned
+        app.update();
+        app.update();
+        let mut gamepads = app.world_mut().query::<&Gamepad>();
+        assert_eq!(gamepads.iter(app.world()).count(), 0);
+        assert!(app.world().get_entity(virtual_gamepads[0]).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Reflection helpers shared by methods that read components, resources and states by type path
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectSerializer;
+use bevy::reflect::{PartialReflect, TypeRegistration, TypeRegistry};
+use bevy::remote::{BrpError, error_codes};
+use serde_json::Value;
+
+use crate::discovery::find_registration;
+
+/// Relative tolerance when comparing JSON numbers, enough to absorb `f32` rounding
+const NUMBER_TOLERANCE: f64 = 1e-6;
+
+/// Get the app's type registry, failing instead of panicking when it is missing
+pub fn app_type_registry(world: &World) -> Result<AppTypeRegistry, BrpError> {
+    world
+        .get_resource::<AppTypeRegistry>()
+        .cloned()
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: "AppTypeRegistry is not available".to_string(),
+            data:    None,
+        })
+}
+
+/// Look up a type registration by its full type path
+pub fn registration<'a>(
+    registry: &'a TypeRegistry,
+    type_path: &str,
+) -> Result<&'a TypeRegistration, BrpError> {
+    find_registration(registry, type_path).map_err(|e| BrpError {
+        code:    error_codes::INVALID_PARAMS,
+        message: e.to_string(),
+        data:    Some(Value::Object(e.to_json_error())),
+    })
+}
+
+/// Serialize a reflected value to JSON in the format BRP uses for components
+pub fn to_json(value: &dyn PartialReflect, registry: &TypeRegistry) -> Result<Value, BrpError> {
+    serde_json::to_value(TypedReflectSerializer::new(value, registry)).map_err(|e| BrpError {
+        code:    error_codes::INTERNAL_ERROR,
+        message: format!("Failed to serialize {}: {e}", value.reflect_type_path()),
+        data:    None,
+    })
+}
+
+/// Compare JSON values, treating numbers within a small relative tolerance as equal
+pub fn json_matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(actual), Value::Number(expected)) => {
+            match (actual.as_f64(), expected.as_f64()) {
+                (Some(actual), Some(expected)) => {
+                    let scale = actual.abs().max(expected.abs()).max(1.0);
+                    (actual - expected).abs() <= NUMBER_TOLERANCE * scale
+                }
+                _ => actual == expected,
+            }
+        }
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| json_matches(actual, expected))
+        }
+        (Value::Object(actual), Value::Object(expected)) => {
+            actual.len() == expected.len()
+                && expected.iter().all(|(key, expected)| {
+                    actual
+                        .get(key)
+                        .is_some_and(|actual| json_matches(actual, expected))
+                })
+        }
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_json_matches_with_float_tolerance() {
+        let actual = json!({ "translation": [0.100_000_001_490_116_12, 2.0, 3.0], "name": "a" });
+        assert!(json_matches(
+            &actual,
+            &json!({ "translation": [0.1, 2, 3.0], "name": "a" })
+        ));
+        assert!(!json_matches(
+            &actual,
+            &json!({ "translation": [0.2, 2.0, 3.0], "name": "a" })
+        ));
+        assert!(!json_matches(
+            &actual,
+            &json!({ "translation": [0.1, 2.0, 3.0] })
+        ));
+        assert!(!json_matches(&json!("1"), &json!(1)));
+    }
+}
@@ -1,117 +1,1316 @@
 //! Screenshot handler for BRP extras
 
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use bevy::prelude::*;
 use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::render::camera::RenderTarget;
+use bevy::render::primitives::Aabb;
 use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
 use bevy::tasks::IoTaskPool;
+use bevy::window::{PrimaryWindow, WindowRef};
+use image::DynamicImage;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
-/// Handler for screenshot requests
+use crate::deferred::{DeferredStep, run_deferred, wait_deadline};
+use crate::headless::{HeadlessTarget, require_renderer};
+use crate::output::{
+    check_overwrite, default_output_directory, overwrite_allowed, resolve_output_path,
+    timestamped_path, write_file,
+};
+
+/// Maximum number of screenshots whose status is kept for `brp_extras/screenshot_status`
+const MAX_TRACKED_SCREENSHOTS: usize = 100;
+
+/// JPEG quality used when none is requested
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Image encoding for screenshots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotFormat {
+    /// PNG, lossless
+    #[default]
+    Png,
+    /// JPEG, lossy and without alpha
+    Jpeg,
+    /// WebP, lossless
+    Webp,
+    /// QOI, lossless
+    Qoi,
+    /// Unencoded 8-bit RGBA pixels in row-major order
+    Raw,
+}
+
+impl ScreenshotFormat {
+    /// Format matching a file extension, ignoring case
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            "qoi" => Some(Self::Qoi),
+            "rgba" | "raw" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+
+    /// File extension for the format
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+            Self::Qoi => "qoi",
+            Self::Raw => "rgba",
+        }
+    }
+
+    /// MIME type of the encoded image
+    #[must_use]
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+            Self::Qoi => "image/qoi",
+            Self::Raw => "application/octet-stream",
+        }
+    }
+}
+
+/// Region of a screenshot in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    /// Left edge
+    pub x:      u32,
+    /// Top edge
+    pub y:      u32,
+    /// Width
+    pub width:  u32,
+    /// Height
+    pub height: u32,
+}
+
+impl CropRect {
+    /// Smallest pixel rect containing a rect given in fractional pixels
+    fn enclosing(rect: Rect) -> Self {
+        let min = rect.min.floor().max(Vec2::ZERO);
+        let max = rect.max.ceil().max(min);
+        // Coordinates are non-negative and rounded, so the casts only saturate on huge values
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Self {
+            x:      min.x as u32,
+            y:      min.y as u32,
+            width:  (max.x - min.x) as u32,
+            height: (max.y - min.y) as u32,
+        }
+    }
+}
+
+/// Region of the screenshot to keep
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum CropRequest {
+    /// Pixel rect of the captured image
+    Rect(CropRect),
+    /// Bounds of a UI node
+    Node {
+        /// UI node entity
+        node: Entity,
+    },
+    /// Bounds of a world entity's bounding box projected through the camera
+    Entity {
+        /// Entity with an `Aabb`, such as a mesh or sprite
+        entity: Entity,
+    },
+}
+
+/// How a captured image is cropped, resized and encoded
+#[derive(Debug, Clone, Copy)]
+struct EncodeOptions {
+    format:     ScreenshotFormat,
+    quality:    u8,
+    max_width:  Option<u32>,
+    max_height: Option<u32>,
+    crop:       Option<CropRect>,
+}
+
+/// Request parameters for screenshots
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScreenshotRequest {
+    /// Path or directory to save the screenshot to, named after the current time when omitted
+    ///
+    /// Inline screenshots are only saved when a path is given.
+    #[serde(default)]
+    pub path:       Option<String>,
+    /// Replace an existing file at `path`, defaults to true without an output root and false with
+    /// one
+    #[serde(default)]
+    pub overwrite:  Option<bool>,
+    /// Wait for the capture and return the encoded image in the response
+    #[serde(default)]
+    pub inline:     bool,
+    /// Image encoding, inferred from the path's extension when omitted
+    #[serde(default)]
+    pub format:     Option<ScreenshotFormat>,
+    /// JPEG quality from 1 to 100
+    #[serde(default)]
+    pub quality:    Option<u8>,
+    /// What to capture and which region of it to keep
+    #[serde(flatten)]
+    pub target:     CaptureTarget,
+    /// Downscale the image to at most this width, keeping the aspect ratio
+    #[serde(default)]
+    pub max_width:  Option<u32>,
+    /// Downscale the image to at most this height, keeping the aspect ratio
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// Respond once the screenshot has been saved instead of when the capture starts
+    #[serde(default)]
+    pub wait:       bool,
+    /// Maximum time to wait for an inline or blocking capture in milliseconds
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+}
+
+/// Render target and region captured by a screenshot
 ///
-/// Takes a screenshot of the primary window and saves it to the specified path.
-/// The path parameter must be provided in the request.
-/// File I/O is performed asynchronously to avoid blocking the main thread.
-pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
-    // Check if PNG support is available at runtime
-    if bevy::image::ImageFormat::from_extension("png").is_none() {
-        return Err(BrpError {
-            code:    error_codes::INTERNAL_ERROR,
-            message: "PNG support not available. Enable the 'png' feature in your Bevy dependency"
-                .to_string(),
-            data:    None,
-        });
+/// When no window, camera or image is given the primary window is captured, or the headless render
+/// target when there is no primary window.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CaptureTarget {
+    /// Window to capture instead of the primary window
+    #[serde(default)]
+    pub window: Option<WindowTarget>,
+    /// Camera whose render target should be captured
+    #[serde(default)]
+    pub camera: Option<Entity>,
+    /// Image render target to capture
+    #[serde(default)]
+    pub image:  Option<ImageTarget>,
+    /// Region to keep, applied before downscaling
+    #[serde(default)]
+    pub crop:   Option<CropRequest>,
+}
+
+/// Window to capture, selected by entity or title
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum WindowTarget {
+    /// Window entity
+    Entity(Entity),
+    /// Window title, which must match exactly one window
+    Title(String),
+}
+
+/// Image render target to capture, selected by asset path or asset ID
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ImageTarget {
+    /// Asset path of a loaded image
+    Path(String),
+    /// Asset ID of the image
+    Id(AssetId<Image>),
+}
+
+impl std::fmt::Display for ImageTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "'{path}'"),
+            Self::Id(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// Progress of a screenshot request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotStatus {
+    /// Waiting for the frame to be captured
+    #[default]
+    Pending,
+    /// The frame was captured and is being processed
+    Captured,
+    /// The image was saved to its file
+    Saved,
+    /// Capturing, saving or encoding the image failed
+    Failed,
+}
+
+/// Response for screenshot status requests and blocking screenshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotStatusResponse {
+    /// Whether the screenshot has not failed
+    pub success:    bool,
+    /// Screenshot request ID
+    pub id:         u64,
+    /// Current status
+    pub status:     ScreenshotStatus,
+    /// Absolute path the screenshot is saved to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path:       Option<String>,
+    /// Image encoding
+    pub format:     ScreenshotFormat,
+    /// Image width in pixels after any downscaling, once captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width:      Option<u32>,
+    /// Image height in pixels after any downscaling, once captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height:     Option<u32>,
+    /// Size of the saved file in bytes, once saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Error message, if the screenshot failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error:      Option<String>,
+}
+
+/// Response for inline screenshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineScreenshotResponse {
+    /// Whether the screenshot was captured
+    pub success:    bool,
+    /// Screenshot request ID
+    pub id:         u64,
+    /// Absolute path the screenshot was also saved to, if a path was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path:       Option<String>,
+    /// Encoding of the image data
+    pub format:     ScreenshotFormat,
+    /// MIME type of the image data
+    pub mime_type:  String,
+    /// Image width in pixels
+    pub width:      u32,
+    /// Image height in pixels
+    pub height:     u32,
+    /// Size of the encoded image in bytes
+    pub size_bytes: usize,
+    /// Base64 encoded image data
+    pub data:       String,
+}
+
+/// Progress of a screenshot shared with its capture observer and background task
+type SharedProgress = Arc<Mutex<ScreenshotProgress>>;
+
+/// Progress of a single screenshot
+#[derive(Debug, Default)]
+struct ScreenshotProgress {
+    status:     ScreenshotStatus,
+    format:     ScreenshotFormat,
+    path:       Option<String>,
+    width:      Option<u32>,
+    height:     Option<u32>,
+    size_bytes: Option<u64>,
+    error:      Option<String>,
+    /// Encoded image for inline requests, taken when the response is sent
+    inline:     Option<InlineScreenshotResponse>,
+}
+
+impl ScreenshotProgress {
+    fn to_response(&self, id: u64) -> ScreenshotStatusResponse {
+        ScreenshotStatusResponse {
+            success: self.status != ScreenshotStatus::Failed,
+            id,
+            status: self.status,
+            format: self.format,
+            path: self.path.clone(),
+            width: self.width,
+            height: self.height,
+            size_bytes: self.size_bytes,
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Update shared progress, ignoring a poisoned lock
+fn update_progress(progress: &SharedProgress, update: impl FnOnce(&mut ScreenshotProgress)) {
+    if let Ok(mut guard) = progress.lock() {
+        update(&mut guard);
+    }
+}
+
+/// Resource tracking the status of recent screenshots by request ID
+#[derive(Resource, Default)]
+pub struct ScreenshotTracker {
+    next_id:     u64,
+    screenshots: BTreeMap<u64, SharedProgress>,
+}
+
+impl ScreenshotTracker {
+    /// Start tracking a new screenshot, forgetting the oldest one when the limit is reached
+    fn track(&mut self, path: Option<String>, format: ScreenshotFormat) -> (u64, SharedProgress) {
+        self.next_id += 1;
+        let progress = SharedProgress::new(Mutex::new(ScreenshotProgress {
+            format,
+            path,
+            ..default()
+        }));
+        self.screenshots.insert(self.next_id, progress.clone());
+        while self.screenshots.len() > MAX_TRACKED_SCREENSHOTS {
+            self.screenshots.pop_first();
+        }
+        (self.next_id, progress)
     }
-    // Get the path from params
-    let path = params
+}
+
+/// State of a screenshot request waiting for its capture
+pub struct PendingScreenshot {
+    id:       u64,
+    progress: SharedProgress,
+    inline:   bool,
+    deadline: Instant,
+}
+
+/// Handler for screenshot requests
+///
+/// Takes a screenshot of the primary window, or of the requested window, camera or image render
+/// target, and saves it to the specified path.
+/// File I/O is performed asynchronously to avoid blocking the main thread. The response
+/// includes an `id` that can be passed to `brp_extras/screenshot_status`.
+///
+/// With `wait` set the handler responds once the file has been saved. With `inline` set it
+/// waits for the capture and returns the image as base64 encoded data, optionally also saving
+/// it to `path`.
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult<Option<Value>> {
+    run_deferred(
+        world,
+        params,
+        |world, params| match start_screenshot(world, params) {
+            Ok(step) => step,
+            Err(e) => DeferredStep::Ready(Err(e)),
+        },
+        poll_screenshot,
+    )
+}
+
+/// Handler for screenshot status requests
+///
+/// Reports whether the screenshot with the given ID is pending, captured, saved or failed.
+#[allow(clippy::needless_pass_by_ref_mut)]
+pub fn status_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let id = params
         .as_ref()
-        .and_then(|v| v.get("path"))
-        .and_then(|v| v.as_str())
+        .and_then(|v| v.get("id"))
+        .and_then(Value::as_u64)
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Missing 'id' parameter".to_string(),
+            data:    None,
+        })?;
+
+    let response = world
+        .get_resource::<ScreenshotTracker>()
+        .and_then(|tracker| tracker.screenshots.get(&id))
+        .and_then(|progress| progress.lock().ok().map(|guard| guard.to_response(id)))
         .ok_or_else(|| BrpError {
             code:    error_codes::INVALID_PARAMS,
-            message: "Missing 'path' parameter".to_string(),
+            message: format!("No screenshot found with ID {id}"),
             data:    None,
         })?;
 
-    // Convert to absolute path
-    let path_buf = std::path::Path::new(path);
-    let absolute_path = if path_buf.is_absolute() {
-        path_buf.to_path_buf()
+    serialize_response(response)
+}
+
+fn serialize_response(response: impl Serialize) -> BrpResult {
+    serde_json::to_value(response).map_err(|e| BrpError {
+        code:    error_codes::INTERNAL_ERROR,
+        message: format!("Failed to serialize screenshot response: {e}"),
+        data:    None,
+    })
+}
+
+fn start_screenshot(
+    world: &mut World,
+    params: Option<Value>,
+) -> Result<DeferredStep<PendingScreenshot>, BrpError> {
+    let request: ScreenshotRequest = params.map_or_else(
+        || Ok(ScreenshotRequest::default()),
+        |value| {
+            serde_json::from_value(value).map_err(|e| BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!("Invalid screenshot parameters: {e}"),
+                data:    None,
+            })
+        },
+    )?;
+
+    let deadline = wait_deadline(request.timeout_ms)?;
+
+    let mut options = encode_options(&request)?;
+    options.crop = resolve_crop(world, &request.target)?;
+
+    // Resolve the path inside the output root, naming the file after the current time when
+    // only a directory or no path at all was given
+    let extension = options.format.extension();
+    let output_path = match request.path.as_deref() {
+        Some(path) => {
+            let resolved = resolve_output_path(world, path)?;
+            if path.ends_with(['/', '\\']) || resolved.is_dir() {
+                Some(timestamped_path(&resolved, "screenshot", extension))
+            } else {
+                Some(resolved)
+            }
+        }
+        None if request.inline => None,
+        None => Some(timestamped_path(
+            &default_output_directory(world)?,
+            "screenshot",
+            extension,
+        )),
+    };
+    let overwrite = overwrite_allowed(world, request.overwrite);
+    if let Some(path) = &output_path {
+        check_overwrite(path, overwrite)?;
+    }
+    let absolute_path_str = output_path.map(|path| path.to_string_lossy().to_string());
+
+    // Log the screenshot request
+    if let Some(path) = &absolute_path_str {
+        info!("Screenshot requested for: {}", path);
     } else {
-        std::env::current_dir()
-            .map_err(|e| BrpError {
+        info!("Inline screenshot requested");
+    }
+
+    let (target, target_description) = resolve_target(world, &request.target)?;
+
+    let (id, progress) = world
+        .get_resource_or_init::<ScreenshotTracker>()
+        .track(absolute_path_str.clone(), options.format);
+    let entity = spawn_screenshot(
+        world,
+        Screenshot(target),
+        absolute_path_str.clone(),
+        overwrite,
+        options,
+        request.inline,
+        progress.clone(),
+    );
+
+    info!(
+        "Screenshot {id} of {target_description} entity spawned with ID: {:?}",
+        entity
+    );
+
+    if !request.inline && !request.wait {
+        return Ok(DeferredStep::Ready(Ok(json!({
+            "success": true,
+            "id": id,
+            "path": absolute_path_str,
+            "format": options.format,
+            "target": target_description,
+            "crop": options.crop,
+            "working_directory": std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("unknown")).to_string_lossy(),
+            "note": "Screenshot capture initiated. File I/O will be performed asynchronously on background thread. Use brp_extras/screenshot_status to check progress."
+        }))));
+    }
+
+    Ok(DeferredStep::Pending(PendingScreenshot {
+        id,
+        progress,
+        inline: request.inline,
+        deadline,
+    }))
+}
+
+fn poll_screenshot(
+    _world: &mut World,
+    pending: PendingScreenshot,
+) -> DeferredStep<PendingScreenshot> {
+    let result = match pending.progress.lock() {
+        Ok(mut progress) => match progress.status {
+            ScreenshotStatus::Failed => Some(Err(BrpError {
                 code:    error_codes::INTERNAL_ERROR,
-                message: format!("Failed to get current directory: {e}"),
-                data:    None,
-            })?
-            .join(path_buf)
+                message: progress
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Screenshot failed".to_string()),
+                data:    Some(json!({ "id": pending.id })),
+            })),
+            _ if pending.inline => progress.inline.take().map(|mut response| {
+                response.id = pending.id;
+                serialize_response(response)
+            }),
+            ScreenshotStatus::Saved => Some(serialize_response(progress.to_response(pending.id))),
+            _ => None,
+        },
+        Err(_) => Some(Err(BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: format!("Status of screenshot {} is unavailable", pending.id),
+            data:    None,
+        })),
     };
 
-    let absolute_path_str = absolute_path.to_string_lossy().to_string();
+    match result {
+        Some(result) => DeferredStep::Ready(result),
+        None if Instant::now() >= pending.deadline => DeferredStep::Ready(Err(BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: format!(
+                "Timed out waiting for screenshot {}. Screenshots require a running renderer and \
+                 a visible window, or headless mode enabled with BrpExtrasPlugin::with_headless",
+                pending.id
+            ),
+            data:    Some(json!({ "id": pending.id })),
+        })),
+        None => DeferredStep::Pending(pending),
+    }
+}
 
-    // Log the screenshot request
-    info!("Screenshot requested for: {}", absolute_path_str);
+/// Resolve the encoding for a request, inferring the format from the path's extension
+fn encode_options(request: &ScreenshotRequest) -> Result<EncodeOptions, BrpError> {
+    let invalid = |message: String| BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    };
 
-    // Check if we have a primary window
-    let window_exists = world.query::<&Window>().iter(world).any(|w| {
-        info!(
-            "Found window - resolution: {:?}, visible: {:?}",
-            w.resolution, w.visible
-        );
-        true
-    });
+    let extension = request
+        .path
+        .as_deref()
+        .and_then(|path| Path::new(path).extension())
+        .map(|extension| extension.to_string_lossy().to_string());
+    let extension_format = extension
+        .as_deref()
+        .and_then(ScreenshotFormat::from_extension);
+
+    let format = match (request.format, extension_format) {
+        (Some(format), Some(inferred)) if format != inferred => {
+            return Err(invalid(format!(
+                "Format '{}' does not match the '.{}' file extension",
+                format.mime_type(),
+                extension.unwrap_or_default()
+            )));
+        }
+        (Some(format), _) | (None, Some(format)) => format,
+        (None, None) => match extension {
+            Some(extension) => {
+                return Err(invalid(format!(
+                    "Unsupported screenshot file extension '.{extension}'. Use png, jpg, webp, \
+                     qoi or rgba, or specify 'format'"
+                )));
+            }
+            None => ScreenshotFormat::Png,
+        },
+    };
+
+    let quality = match (request.quality, format) {
+        (None, _) => DEFAULT_JPEG_QUALITY,
+        (Some(quality), ScreenshotFormat::Jpeg) if (1..=100).contains(&quality) => quality,
+        (Some(quality), ScreenshotFormat::Jpeg) => {
+            return Err(invalid(format!(
+                "JPEG quality {quality} is out of range, expected 1 to 100"
+            )));
+        }
+        (Some(_), _) => {
+            return Err(invalid(
+                "'quality' is only supported for the jpeg format".to_string(),
+            ));
+        }
+    };
 
-    if !window_exists {
-        warn!("No windows found in the world!");
+    if request.max_width == Some(0) || request.max_height == Some(0) {
+        return Err(invalid(
+            "'max_width' and 'max_height' must be greater than 0".to_string(),
+        ));
     }
 
-    // Spawn a screenshot entity with an observer to handle the capture
-    let path_for_observer = absolute_path_str.clone();
-    let entity = world
-        .spawn((
-            Screenshot::primary_window(),
-            Name::new(format!("Screenshot_{absolute_path_str}")),
-        ))
+    Ok(EncodeOptions {
+        format,
+        quality,
+        max_width: request.max_width,
+        max_height: request.max_height,
+        crop: None,
+    })
+}
+
+/// Resolve the requested crop region to a pixel rect
+///
+/// World entities are projected through the `camera` parameter when given, otherwise through the
+/// active camera with the lowest order.
+pub fn resolve_crop(
+    world: &mut World,
+    request: &CaptureTarget,
+) -> Result<Option<CropRect>, BrpError> {
+    let invalid = |message: String| BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    };
+
+    let rect = match request.crop {
+        None => return Ok(None),
+        Some(CropRequest::Rect(rect)) => rect,
+        Some(CropRequest::Node { node }) => {
+            let (computed, transform) = world
+                .query::<(&ComputedNode, &GlobalTransform)>()
+                .get(world, node)
+                .map_err(|_| invalid(format!("Entity {node} is not a UI node")))?;
+            // UI layout is in physical pixels, with the transform at the node's center
+            let center = transform.translation().truncate();
+            CropRect::enclosing(Rect::from_center_size(center, computed.size()))
+        }
+        Some(CropRequest::Entity { entity }) => {
+            let (aabb, transform) = world
+                .query::<(&Aabb, &GlobalTransform)>()
+                .get(world, entity)
+                .map(|(aabb, transform)| (*aabb, *transform))
+                .map_err(|_| invalid(format!("Entity {entity} has no bounding box")))?;
+
+            let mut cameras = world.query::<(Entity, &Camera, &GlobalTransform)>();
+            let (camera, camera_transform) = cameras
+                .iter(world)
+                .filter(|(camera_entity, camera, _)| {
+                    request
+                        .camera
+                        .map_or(camera.is_active, |requested| requested == *camera_entity)
+                })
+                .min_by_key(|(_, camera, _)| camera.order)
+                .map(|(_, camera, transform)| (camera, transform))
+                .ok_or_else(|| {
+                    invalid("No camera found to project the entity through".to_string())
+                })?;
+            let scale = camera.target_scaling_factor().unwrap_or(1.0);
+
+            let center = Vec3::from(aabb.center);
+            let half_extents = Vec3::from(aabb.half_extents);
+            let mut bounds = Rect::EMPTY;
+            for corner in 0..8_u8 {
+                let sign = Vec3::new(
+                    if corner & 1 == 0 { -1.0 } else { 1.0 },
+                    if corner & 2 == 0 { -1.0 } else { 1.0 },
+                    if corner & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                let world_position = transform.transform_point(center + sign * half_extents);
+                let viewport_position = camera
+                    .world_to_viewport(camera_transform, world_position)
+                    .map_err(|e| {
+                        invalid(format!(
+                            "Entity {entity} can't be projected onto the screen: {e:?}"
+                        ))
+                    })?;
+                bounds = bounds.union_point(viewport_position * scale);
+            }
+            CropRect::enclosing(bounds)
+        }
+    };
+
+    if rect.width == 0 || rect.height == 0 {
+        return Err(invalid("Crop region is empty".to_string()));
+    }
+    Ok(Some(rect))
+}
+
+/// Resolve the render target to capture, defaulting to the primary window
+///
+/// Without a primary window the headless render target is captured, if headless mode is enabled.
+/// Returns the target along with a description for logs and responses.
+pub fn resolve_target(
+    world: &mut World,
+    request: &CaptureTarget,
+) -> Result<(RenderTarget, String), BrpError> {
+    require_renderer(world)?;
+
+    let invalid = |message: String| BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    };
+
+    match (&request.window, request.camera, &request.image) {
+        (None, None, None) => {
+            let has_primary_window = world
+                .query_filtered::<(), With<PrimaryWindow>>()
+                .iter(world)
+                .next()
+                .is_some();
+            if has_primary_window {
+                return Ok((
+                    RenderTarget::Window(WindowRef::Primary),
+                    "primary window".to_string(),
+                ));
+            }
+
+            let headless = world
+                .get_resource::<HeadlessTarget>()
+                .ok_or_else(|| BrpError {
+                    code:    error_codes::INVALID_REQUEST,
+                    message: "No primary window to capture. Enable headless mode with \
+                          BrpExtrasPlugin::with_headless or pass 'camera' or 'image'"
+                        .to_string(),
+                    data:    None,
+                })?;
+            Ok((
+                RenderTarget::Image(headless.image.clone().into()),
+                "headless render target".to_string(),
+            ))
+        }
+        (Some(WindowTarget::Entity(entity)), None, None) => {
+            if world.get::<Window>(*entity).is_none() {
+                return Err(invalid(format!("Entity {entity} is not a window")));
+            }
+            Ok((
+                RenderTarget::Window(WindowRef::Entity(*entity)),
+                format!("window {entity}"),
+            ))
+        }
+        (Some(WindowTarget::Title(title)), None, None) => {
+            let matches: Vec<Entity> = world
+                .query::<(Entity, &Window)>()
+                .iter(world)
+                .filter(|(_, window)| window.title == *title)
+                .map(|(entity, _)| entity)
+                .collect();
+            match matches.as_slice() {
+                [entity] => Ok((
+                    RenderTarget::Window(WindowRef::Entity(*entity)),
+                    format!("window '{title}'"),
+                )),
+                [] => Err(invalid(format!("No window found with title '{title}'"))),
+                _ => Err(invalid(format!(
+                    "{} windows have the title '{title}', select one by entity instead",
+                    matches.len()
+                ))),
+            }
+        }
+        (None, Some(entity), None) => {
+            let camera = world
+                .get::<Camera>(entity)
+                .ok_or_else(|| invalid(format!("Entity {entity} is not a camera")))?;
+            Ok((camera.target.clone(), format!("camera {entity}")))
+        }
+        (None, None, Some(image)) => {
+            let handle = match image {
+                ImageTarget::Path(path) => world
+                    .get_resource::<AssetServer>()
+                    .and_then(|asset_server| asset_server.get_handle::<Image>(path.as_str())),
+                ImageTarget::Id(id) => world
+                    .get_resource_mut::<Assets<Image>>()
+                    .and_then(|mut images| images.get_strong_handle(*id)),
+            }
+            .ok_or_else(|| invalid(format!("No image found for {image}")))?;
+            Ok((RenderTarget::Image(handle.into()), format!("image {image}")))
+        }
+        _ => Err(invalid(
+            "Specify at most one of 'window', 'camera' and 'image'".to_string(),
+        )),
+    }
+}
+
+/// Spawn a screenshot entity that hands the captured image to `on_capture`
+///
+/// `on_capture` runs on a background thread to avoid blocking the main thread with file I/O.
+pub fn spawn_capture(
+    world: &mut World,
+    screenshot: Screenshot,
+    name: String,
+    on_capture: impl Fn(Image) + Clone + Send + Sync + 'static,
+) -> Entity {
+    world
+        .spawn((screenshot, Name::new(name)))
         .observe(move |trigger: Trigger<ScreenshotCaptured>| {
-            info!("Screenshot captured! Starting async save to: {path_for_observer}");
+            info!("Screenshot captured! Starting async processing");
             let img = trigger.event().0.clone();
-            let path_clone = path_for_observer.clone();
+            let on_capture = on_capture.clone();
 
             // Move file I/O to background thread to avoid blocking main thread
             IoTaskPool::get()
-                .spawn(async move {
-                    match img.try_into_dynamic() {
-                        Ok(dyn_img) => {
-                            // Create parent directory if needed
-                            if let Some(parent) = std::path::Path::new(&path_clone).parent() {
-                                if let Err(e) = std::fs::create_dir_all(parent) {
-                                    error!("Failed to create directory for screenshot {path_clone}: {e}");
-                                    return;
-                                }
-                            }
-
-                            // Save the image
-                            match dyn_img.save(&path_clone) {
-                                Ok(()) => {
-                                    info!("Screenshot successfully saved to: {path_clone}");
-                                }
-                                Err(e) => {
-                                    error!("Failed to save screenshot to {path_clone}: {e}");
-                                }
-                            }
-                        }
-                        Err(e) => error!("Failed to convert screenshot to dynamic image: {e}"),
-                    }
-                })
+                .spawn(async move { on_capture(img) })
                 .detach();
         })
-        .id();
+        .id()
+}
 
-    info!("Screenshot entity spawned with ID: {:?}", entity);
+/// Spawn a screenshot entity that saves or encodes the capture, recording its progress
+fn spawn_screenshot(
+    world: &mut World,
+    screenshot: Screenshot,
+    path: Option<String>,
+    overwrite: bool,
+    options: EncodeOptions,
+    inline: bool,
+    progress: SharedProgress,
+) -> Entity {
+    let name = path.as_ref().map_or_else(
+        || "Screenshot_inline".to_string(),
+        |path| format!("Screenshot_{path}"),
+    );
 
-    Ok(json!({
-        "success": true,
-        "path": absolute_path_str,
-        "working_directory": std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("unknown")).to_string_lossy(),
-        "note": "Screenshot capture initiated. File I/O will be performed asynchronously on background thread."
-    }))
+    spawn_capture(world, screenshot, name, move |img| {
+        update_progress(&progress, |progress| {
+            progress.status = ScreenshotStatus::Captured;
+            progress.width = Some(img.width());
+            progress.height = Some(img.height());
+        });
+        process_capture(img, path.as_deref(), overwrite, options, inline, &progress);
+    })
+}
+
+/// Resize, encode and save a captured image, recording the outcome in `progress`
+fn process_capture(
+    img: Image,
+    path: Option<&str>,
+    overwrite: bool,
+    options: EncodeOptions,
+    inline: bool,
+    progress: &SharedProgress,
+) {
+    let result = img
+        .try_into_dynamic()
+        .map_err(|e| format!("Failed to convert screenshot to dynamic image: {e}"))
+        .and_then(|dyn_img| crop(dyn_img, options.crop))
+        .map(|dyn_img| downscale(dyn_img, options))
+        .and_then(|dyn_img| {
+            let bytes = encode_image(&dyn_img, options)?;
+            update_progress(progress, |progress| {
+                progress.width = Some(dyn_img.width());
+                progress.height = Some(dyn_img.height());
+            });
+
+            if let Some(path) = path {
+                save_image(&bytes, path, overwrite)?;
+                update_progress(progress, |progress| {
+                    progress.status = ScreenshotStatus::Saved;
+                    progress.size_bytes = Some(bytes.len() as u64);
+                });
+            }
+
+            Ok(inline.then(|| InlineScreenshotResponse {
+                success:    true,
+                id:         0,
+                path:       path.map(str::to_string),
+                format:     options.format,
+                mime_type:  options.format.mime_type().to_string(),
+                width:      dyn_img.width(),
+                height:     dyn_img.height(),
+                size_bytes: bytes.len(),
+                data:       STANDARD.encode(&bytes),
+            }))
+        });
+
+    match result {
+        Ok(encoded) => update_progress(progress, |progress| progress.inline = encoded),
+        Err(e) => {
+            error!("{e}");
+            update_progress(progress, |progress| {
+                progress.status = ScreenshotStatus::Failed;
+                progress.error = Some(e);
+            });
+        }
+    }
+}
+
+/// Crop, encode and save a captured image, returning the file size in bytes
+pub fn save_capture(
+    img: Image,
+    crop_rect: Option<CropRect>,
+    format: ScreenshotFormat,
+    path: &str,
+    overwrite: bool,
+) -> Result<u64, String> {
+    let dyn_img = img
+        .try_into_dynamic()
+        .map_err(|e| format!("Failed to convert screenshot to dynamic image: {e}"))?;
+    let dyn_img = crop(dyn_img, crop_rect)?;
+    let bytes = encode_image(
+        &dyn_img,
+        EncodeOptions {
+            format,
+            quality: DEFAULT_JPEG_QUALITY,
+            max_width: None,
+            max_height: None,
+            crop: None,
+        },
+    )?;
+    save_image(&bytes, path, overwrite)?;
+    Ok(bytes.len() as u64)
+}
+
+/// Crop an image to the requested region, clamped to the image bounds
+pub fn crop(image: DynamicImage, rect: Option<CropRect>) -> Result<DynamicImage, String> {
+    let Some(rect) = rect else {
+        return Ok(image);
+    };
+    if rect.x >= image.width() || rect.y >= image.height() {
+        return Err(format!(
+            "Crop region at ({}, {}) lies outside the {}x{} screenshot",
+            rect.x,
+            rect.y,
+            image.width(),
+            image.height()
+        ));
+    }
+    let width = rect.width.min(image.width() - rect.x);
+    let height = rect.height.min(image.height() - rect.y);
+    Ok(image.crop_imm(rect.x, rect.y, width, height))
+}
+
+/// Downscale an image to fit within the requested bounds, keeping the aspect ratio
+fn downscale(image: DynamicImage, options: EncodeOptions) -> DynamicImage {
+    let max_width = options.max_width.unwrap_or(u32::MAX);
+    let max_height = options.max_height.unwrap_or(u32::MAX);
+    if image.width() <= max_width && image.height() <= max_height {
+        image
+    } else {
+        image.resize(max_width, max_height, FilterType::Triangle)
+    }
+}
+
+/// Save encoded image bytes, creating parent directories as needed
+fn save_image(bytes: &[u8], path: &str, overwrite: bool) -> Result<(), String> {
+    // Create parent directory if needed
+    if let Some(parent) = Path::new(path).parent()
+        && let Err(e) = std::fs::create_dir_all(parent)
+    {
+        error!("Failed to create directory for screenshot {path}: {e}");
+        return Err(format!(
+            "Failed to create directory for screenshot {path}: {e}"
+        ));
+    }
+
+    // Save the image
+    match write_file(Path::new(path), bytes, overwrite) {
+        Ok(()) => {
+            info!("Screenshot successfully saved to: {path}");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to save screenshot to {path}: {e}");
+            Err(format!("Failed to save screenshot to {path}: {e}"))
+        }
+    }
+}
+
+/// Encode an image in the requested format
+fn encode_image(image: &DynamicImage, options: EncodeOptions) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut cursor = Cursor::new(&mut bytes);
+    let encoded = match options.format {
+        ScreenshotFormat::Png => image.write_to(&mut cursor, image::ImageFormat::Png),
+        // JPEG has no alpha channel
+        ScreenshotFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut cursor, options.quality)),
+        ScreenshotFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut cursor, image::ImageFormat::WebP),
+        ScreenshotFormat::Qoi => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut cursor, image::ImageFormat::Qoi),
+        ScreenshotFormat::Raw => return Ok(image.to_rgba8().into_raw()),
+    };
+    encoded.map_err(|e| format!("Failed to encode screenshot: {e}"))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    use super::*;
+    use crate::deferred::with_request_id;
+    use crate::output::{OutputRoot, unique_temp_dir};
+
+    fn test_image() -> Image {
+        Image::new_fill(
+            Extent3d {
+                width:                 4,
+                height:                2,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn options(format: ScreenshotFormat) -> EncodeOptions {
+        EncodeOptions {
+            format,
+            quality: DEFAULT_JPEG_QUALITY,
+            max_width: None,
+            max_height: None,
+            crop: None,
+        }
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_encode_image_formats() {
+        let image = DynamicImage::new_rgba8(4, 2);
+        let encode = |format| encode_image(&image, options(format)).expect("Expected encoding");
+
+        assert!(encode(ScreenshotFormat::Png).starts_with(b"\x89PNG"));
+        assert!(encode(ScreenshotFormat::Jpeg).starts_with(&[0xFF, 0xD8]));
+        assert!(encode(ScreenshotFormat::Webp).starts_with(b"RIFF"));
+        assert!(encode(ScreenshotFormat::Qoi).starts_with(b"qoif"));
+        assert_eq!(encode(ScreenshotFormat::Raw).len(), 4 * 2 * 4);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_inline_capture_is_downscaled() {
+        let progress = SharedProgress::default();
+        let options = EncodeOptions {
+            max_width: Some(2),
+            ..options(ScreenshotFormat::Png)
+        };
+        process_capture(test_image(), None, false, options, true, &progress);
+
+        let response = progress
+            .lock()
+            .expect("Expected progress")
+            .inline
+            .take()
+            .expect("Expected inline data");
+        assert_eq!((response.width, response.height), (2, 1));
+        assert_eq!(response.mime_type, "image/png");
+        let bytes = STANDARD
+            .decode(&response.data)
+            .expect("Expected valid base64");
+        assert_eq!(bytes.len(), response.size_bytes);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_format_inferred_from_extension() {
+        let request = |params: Value| {
+            encode_options(&serde_json::from_value(params).expect("Expected valid request"))
+        };
+
+        let inferred =
+            request(json!({ "path": "shot.JPG", "quality": 50 })).expect("Expected JPEG");
+        assert_eq!(inferred.format, ScreenshotFormat::Jpeg);
+        assert_eq!(inferred.quality, 50);
+        let inferred = request(json!({ "path": "shot" })).expect("Expected PNG default");
+        assert_eq!(inferred.format, ScreenshotFormat::Png);
+
+        assert!(request(json!({ "path": "shot.png", "format": "qoi" })).is_err());
+        assert!(request(json!({ "path": "shot.tiff" })).is_err());
+        assert!(request(json!({ "path": "shot.png", "quality": 50 })).is_err());
+        assert!(request(json!({ "inline": true, "format": "jpeg", "quality": 0 })).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_output_path_defaults_and_limits() {
+        let root = unique_temp_dir("screenshot_root");
+        let mut world = World::new();
+        world.spawn((Window::default(), PrimaryWindow));
+        world.insert_resource(OutputRoot(Some(root.clone())));
+
+        // Without a path the file is named after the current time inside the output root
+        let response = handler(
+            In(with_request_id(json!({ "format": "qoi" }), 1)),
+            &mut world,
+        )
+        .expect("Expected success")
+        .expect("Expected immediate response");
+        let path = response["path"].as_str().expect("Expected a path");
+        assert!(path.starts_with(&*root.to_string_lossy()));
+        assert!(path.contains("screenshot_"));
+        assert_eq!(
+            Path::new(path).extension().and_then(|e| e.to_str()),
+            Some("qoi")
+        );
+
+        let error = handler(
+            In(with_request_id(json!({ "path": "../escape.png" }), 2)),
+            &mut world,
+        )
+        .expect_err("Expected path outside the root to be rejected");
+        assert_eq!(error.code, error_codes::INVALID_PARAMS);
+
+        let error = handler(
+            In(with_request_id(
+                json!({ "inline": true, "timeout_ms": 120_000 }),
+                3,
+            )),
+            &mut world,
+        )
+        .expect_err("Expected timeout error");
+        assert!(error.message.contains("exceeds maximum allowed timeout"));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_inline_screenshot_waits_for_capture() {
+        let mut world = World::new();
+        world.spawn((Window::default(), PrimaryWindow));
+        let params = with_request_id(json!({ "inline": true, "timeout_ms": 0 }), 1);
+
+        // Without a renderer the capture never completes, so the request times out
+        let first = handler(In(params.clone()), &mut world).expect("Expected pending");
+        assert!(first.is_none());
+        let error = handler(In(params), &mut world).expect_err("Expected timeout");
+        assert!(error.message.contains("Timed out"));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_screenshot_status_tracks_progress() {
+        let mut world = World::new();
+        world.spawn((Window::default(), PrimaryWindow));
+        let directory = unique_temp_dir("screenshot_status");
+        let path = directory.join("status.png").to_string_lossy().to_string();
+
+        let response = handler(In(with_request_id(json!({ "path": path }), 1)), &mut world)
+            .expect("Expected success")
+            .expect("Expected immediate response");
+        let id = response["id"].as_u64().expect("Expected an ID");
+
+        let status =
+            status_handler(In(Some(json!({ "id": id }))), &mut world).expect("Expected status");
+        assert_eq!(status["status"], "pending");
+
+        // Simulate the capture completing
+        let progress = world.resource::<ScreenshotTracker>().screenshots[&id].clone();
+        process_capture(
+            test_image(),
+            Some(&path),
+            true,
+            options(ScreenshotFormat::Png),
+            false,
+            &progress,
+        );
+
+        let status =
+            status_handler(In(Some(json!({ "id": id }))), &mut world).expect("Expected status");
+        assert_eq!(status["status"], "saved");
+        assert!(status["size_bytes"].as_u64().expect("Expected file size") > 0);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let error = status_handler(In(Some(json!({ "id": id + 1 }))), &mut world)
+            .expect_err("Expected unknown ID error");
+        assert!(error.message.contains("No screenshot found"));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_failed_capture_reports_error() {
+        let mut world = World::new();
+        world.spawn((Window::default(), PrimaryWindow));
+        let params = with_request_id(
+            json!({ "path": "/dev/null/unwritable/shot.png", "wait": true }),
+            1,
+        );
+
+        let first = handler(In(params.clone()), &mut world).expect("Expected pending");
+        assert!(first.is_none());
+
+        let progress = world.resource::<ScreenshotTracker>().screenshots[&1].clone();
+        process_capture(
+            test_image(),
+            Some("/dev/null/unwritable/shot.png"),
+            false,
+            options(ScreenshotFormat::Png),
+            false,
+            &progress,
+        );
+
+        let error = handler(In(params), &mut world).expect_err("Expected failure");
+        assert_eq!(error.data, Some(json!({ "id": 1 })));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_resolve_screenshot_target() {
+        let mut world = World::new();
+        let window = world
+            .spawn(Window {
+                title: "Tools".to_string(),
+                ..default()
+            })
+            .id();
+        let camera = world.spawn(Camera::default()).id();
+        let mut resolve = |params: Value| {
+            let request: ScreenshotRequest =
+                serde_json::from_value(params).expect("Expected valid request");
+            resolve_target(&mut world, &request.target).map(|(target, _)| target)
+        };
+
+        let target = resolve(json!({ "window": "Tools" })).expect("Expected window");
+        assert!(matches!(target, RenderTarget::Window(WindowRef::Entity(e)) if e == window));
+        let target = resolve(json!({ "window": window })).expect("Expected window");
+        assert!(matches!(target, RenderTarget::Window(WindowRef::Entity(e)) if e == window));
+        let target = resolve(json!({ "camera": camera })).expect("Expected camera target");
+        assert!(matches!(target, RenderTarget::Window(WindowRef::Primary)));
+
+        assert!(resolve(json!({ "window": "Missing" })).is_err());
+        assert!(resolve(json!({ "camera": window })).is_err());
+        assert!(resolve(json!({ "window": window, "camera": camera })).is_err());
+        assert!(resolve(json!({ "image": "render_target.png" })).is_err());
+
+        // Without a primary window only the headless render target can be captured
+        assert!(resolve(json!({})).is_err());
+        let image = Handle::<Image>::default();
+        world.insert_resource(HeadlessTarget {
+            image: image.clone(),
+            size:  UVec2::new(64, 32),
+        });
+        let target = resolve_target(&mut world, &CaptureTarget::default())
+            .map(|(target, _)| target)
+            .expect("Expected headless target");
+        assert!(matches!(target, RenderTarget::Image(target) if target.handle == image));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_crop_to_node_bounds() {
+        let mut world = World::new();
+        let node = world
+            .spawn((
+                ComputedNode {
+                    size: Vec2::new(20.0, 10.0),
+                    ..default()
+                },
+                GlobalTransform::from_xyz(15.0, 5.0, 0.0),
+            ))
+            .id();
+        let request: ScreenshotRequest =
+            serde_json::from_value(json!({ "inline": true, "crop": { "node": node } }))
+                .expect("Expected valid request");
+
+        let rect = resolve_crop(&mut world, &request.target)
+            .expect("Expected node bounds")
+            .expect("Expected a crop region");
+        assert_eq!(
+            rect,
+            CropRect {
+                x:      5,
+                y:      0,
+                width:  20,
+                height: 10,
+            }
+        );
+
+        // The crop region is clamped to the captured image
+        let progress = SharedProgress::default();
+        let options = EncodeOptions {
+            crop: Some(CropRect {
+                x:      1,
+                y:      1,
+                width:  20,
+                height: 20,
+            }),
+            ..options(ScreenshotFormat::Raw)
+        };
+        process_capture(test_image(), None, false, options, true, &progress);
+        let response = progress
+            .lock()
+            .expect("Expected progress")
+            .inline
+            .take()
+            .expect("Expected inline data");
+        assert_eq!((response.width, response.height), (3, 1));
+    }
 }
@@ -0,0 +1,298 @@
+//! Finding entities by `Name`, `Name` path and component presence
+//!
+//! A selector is a `/`-separated path of `Name` patterns that ends at the entity, such as
+//! `"Player"` or `"UI/MainMenu/PlayButton"`. Each segment is matched against the names of
+//! successive named ancestors, skipping ancestors without a `Name`. Segments may use `*` for any
+//! run of characters, `?` for a single character and `**` for any number of levels. A leading
+//! `/` anchors the path at a root entity.
+
+use bevy::ecs::component::ComponentId;
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::reflection::{app_type_registry, registration};
+
+/// Path segment matching any number of levels
+const ANY_LEVELS: &str = "**";
+
+/// Request parameters for `brp_extras/find_entities`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FindEntitiesRequest {
+    /// `Name` or `Name` path selector, such as `"UI/MainMenu/PlayButton"` or `"Enemy*"`
+    pub selector: Option<String>,
+    /// Component type paths an entity must have
+    #[serde(default)]
+    pub with:     Vec<String>,
+    /// Component type paths an entity must not have
+    #[serde(default)]
+    pub without:  Vec<String>,
+}
+
+/// An entity matched by `brp_extras/find_entities`
+#[derive(Debug, Clone, Serialize)]
+pub struct FoundEntity {
+    /// Entity ID
+    pub entity: Entity,
+    /// The entity's `Name`, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name:   Option<String>,
+    /// Names of the entity's named ancestors and itself, joined with `/`
+    pub path:   String,
+}
+
+/// Handler for finding entities by selector and component filters
+#[allow(clippy::needless_pass_by_ref_mut)]
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: FindEntitiesRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid find_entities parameters: {e}"),
+            data:    None,
+        })?,
+        None => FindEntitiesRequest::default(),
+    };
+    if request.selector.is_none() && request.with.is_empty() && request.without.is_empty() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Specify a 'selector', 'with' or 'without' to find entities".to_string(),
+            data:    None,
+        });
+    }
+
+    let type_registry = app_type_registry(world)?;
+    let entities = find_entities(world, &type_registry.read(), &request)?;
+
+    Ok(json!({
+        "success": true,
+        "count": entities.len(),
+        "entities": entities
+    }))
+}
+
+/// Find entities matching the request, sorted by entity
+fn find_entities(
+    world: &World,
+    registry: &TypeRegistry,
+    request: &FindEntitiesRequest,
+) -> Result<Vec<FoundEntity>, BrpError> {
+    // A component that was never added to an entity has no ID, so no entity has it
+    let with = component_ids(world, registry, &request.with)?;
+    let without: Vec<ComponentId> = component_ids(world, registry, &request.without)?
+        .into_iter()
+        .flatten()
+        .collect();
+    if with.iter().any(Option::is_none) {
+        return Ok(Vec::new());
+    }
+
+    let mut found: Vec<FoundEntity> = world
+        .iter_entities()
+        .filter(|entity| with.iter().flatten().all(|&id| entity.contains_id(id)))
+        .filter(|entity| !without.iter().any(|&id| entity.contains_id(id)))
+        .filter(|entity| request.selector.is_none() || entity.contains::<Name>())
+        .filter_map(|entity| {
+            // Only entities that passed every cheaper filter get their path built
+            let names = name_path(world, entity.id());
+            if request
+                .selector
+                .as_deref()
+                .is_some_and(|selector| !matches_selector(selector, &names))
+            {
+                return None;
+            }
+            Some(FoundEntity {
+                entity: entity.id(),
+                name:   entity.get::<Name>().map(|name| name.as_str().to_string()),
+                path:   names.join("/"),
+            })
+        })
+        .collect();
+    found.sort_by_key(|found| found.entity);
+    Ok(found)
+}
+
+/// Find entities whose `Name` path matches a selector, sorted by entity
+pub fn select(world: &World, selector: &str) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = world
+        .iter_entities()
+        .filter(EntityRef::contains::<Name>)
+        .map(|entity| entity.id())
+        .filter(|&entity| matches_selector(selector, &name_path(world, entity)))
+        .collect();
+    entities.sort();
+    entities
+}
+
+fn component_ids(
+    world: &World,
+    registry: &TypeRegistry,
+    type_paths: &[String],
+) -> Result<Vec<Option<ComponentId>>, BrpError> {
+    type_paths
+        .iter()
+        .map(|type_path| {
+            registration(registry, type_path)
+                .map(|registration| world.components().get_id(registration.type_id()))
+        })
+        .collect()
+}
+
+/// Names of an entity's named ancestors and the entity itself, from the root down
+fn name_path(world: &World, entity: Entity) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        let Ok(entity_ref) = world.get_entity(entity) else {
+            break;
+        };
+        if let Some(name) = entity_ref.get::<Name>() {
+            names.push(name.as_str());
+        }
+        current = entity_ref.get::<ChildOf>().map(ChildOf::parent);
+    }
+    names.reverse();
+    names
+}
+
+/// Check a selector against an entity's name path
+fn matches_selector(selector: &str, names: &[&str]) -> bool {
+    let (anchored, selector) = selector
+        .strip_prefix('/')
+        .map_or((false, selector), |rest| (true, rest));
+    let mut segments: Vec<&str> = selector.split('/').collect();
+    if !anchored {
+        segments.insert(0, ANY_LEVELS);
+    }
+    matches_segments(&segments, names)
+}
+
+fn matches_segments(segments: &[&str], names: &[&str]) -> bool {
+    match segments.split_first() {
+        None => names.is_empty(),
+        Some((&ANY_LEVELS, rest)) => {
+            (0..=names.len()).any(|skip| matches_segments(rest, &names[skip..]))
+        }
+        Some((segment, rest)) => names.split_first().is_some_and(|(name, names)| {
+            matches_glob(segment, name) && matches_segments(rest, names)
+        }),
+    }
+}
+
+/// Match a name against a pattern where `*` matches any run of characters and `?` one character
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried against
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    p = star;
+                    n = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("Player", "Player"));
+        assert!(!matches_glob("Player", "Player2"));
+        assert!(matches_glob("Enemy*", "EnemyOrc"));
+        assert!(matches_glob("*Button", "PlayButton"));
+        assert!(matches_glob("P?ay*n", "PlayButton"));
+        assert!(matches_glob("*", ""));
+        assert!(!matches_glob("?", ""));
+    }
+
+    #[test]
+    fn test_matches_selector() {
+        let names = ["UI", "MainMenu", "PlayButton"];
+        assert!(matches_selector("PlayButton", &names));
+        assert!(matches_selector("MainMenu/PlayButton", &names));
+        assert!(matches_selector("/UI/MainMenu/PlayButton", &names));
+        assert!(matches_selector("/UI/**/PlayButton", &names));
+        assert!(matches_selector("UI/*/Play*", &names));
+        assert!(!matches_selector("/MainMenu/PlayButton", &names));
+        assert!(!matches_selector("UI/PlayButton", &names));
+        assert!(!matches_selector("MainMenu", &names));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_find_entities() {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Transform>();
+
+        let ui = world.spawn(Name::new("UI")).id();
+        let menu = world.spawn((Name::new("MainMenu"), ChildOf(ui))).id();
+        // Unnamed containers are skipped in name paths
+        let container = world.spawn(ChildOf(menu)).id();
+        let button = world
+            .spawn((
+                Name::new("PlayButton"),
+                Transform::default(),
+                ChildOf(container),
+            ))
+            .id();
+        world.spawn((Name::new("QuitButton"), ChildOf(container)));
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let request = FindEntitiesRequest {
+            selector: Some("UI/MainMenu/PlayButton".to_string()),
+            ..default()
+        };
+        let found = find_entities(&world, &registry.read(), &request).expect("Expected entities");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entity, button);
+
+        let request = FindEntitiesRequest {
+            selector: Some("MainMenu/*Button".to_string()),
+            ..default()
+        };
+        let found = find_entities(&world, &registry.read(), &request).expect("Expected entities");
+        assert_eq!(found.len(), 2);
+
+        let request = FindEntitiesRequest {
+            selector: Some("*Button".to_string()),
+            with: vec![Transform::type_path().to_string()],
+            ..default()
+        };
+        let found = find_entities(&world, &registry.read(), &request).expect("Expected entities");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entity, button);
+        assert_eq!(found[0].path, "UI/MainMenu/PlayButton");
+
+        let request = FindEntitiesRequest {
+            without: vec![Transform::type_path().to_string()],
+            ..default()
+        };
+        let found = find_entities(&world, &registry.read(), &request).expect("Expected entities");
+        assert_eq!(found.len(), 4);
+    }
+}
@@ -1,47 +1,378 @@
 //! Shutdown handler for BRP extras
+//!
+//! A shutdown request sends [`BrpShutdownRequested`] before the app exits. App code that must
+//! finish work first, such as flushing save files, registers a name in [`ShutdownHooks`] and
+//! acknowledges it once done. The exit waits for every registered hook until the deadline.
 
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use bevy::app::AppExit;
 use bevy::prelude::*;
-use bevy::remote::BrpResult;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::Deserialize;
 use serde_json::{Value, json};
 
+/// Frames to wait before exiting when no delay is requested (about 167ms at 60fps)
+const DEFAULT_DELAY_FRAMES: u32 = 10;
+
+/// Frames to wait at minimum so the response is sent before the app exits
+const MIN_DELAY_FRAMES: u32 = 3;
+
+/// Maximum delay in milliseconds
+const MAX_DELAY_MS: u64 = 600_000;
+
+/// Default time to wait for shutdown hooks in milliseconds
+const DEFAULT_HOOK_TIMEOUT_MS: u64 = 10_000;
+
+/// Event sent when a BRP shutdown is requested, before the app exits
+#[derive(Event, Debug, Clone)]
+pub struct BrpShutdownRequested {
+    /// Reason given with the shutdown request
+    pub reason:    Option<String>,
+    /// Exit code the app will exit with
+    pub exit_code: u8,
+    /// Time after which the app exits even if hooks have not acknowledged
+    pub deadline:  Instant,
+}
+
+/// Resource where app code registers work that must finish before a BRP shutdown exits
+///
+/// Hooks are usually registered at startup. After [`BrpShutdownRequested`] is sent, the exit
+/// waits until every registered hook has been acknowledged or the deadline has passed.
+#[derive(Resource, Debug, Default)]
+pub struct ShutdownHooks {
+    registered:   BTreeSet<String>,
+    acknowledged: BTreeSet<String>,
+}
+
+impl ShutdownHooks {
+    /// Register a hook that must be acknowledged before the app exits
+    pub fn register(&mut self, name: impl Into<String>) {
+        self.registered.insert(name.into());
+    }
+
+    /// Remove a hook so shutdowns no longer wait for it
+    pub fn unregister(&mut self, name: &str) {
+        self.registered.remove(name);
+        self.acknowledged.remove(name);
+    }
+
+    /// Mark a hook's work as finished for the pending shutdown
+    pub fn acknowledge(&mut self, name: &str) {
+        if self.registered.contains(name) {
+            self.acknowledged.insert(name.to_string());
+        } else {
+            warn!("Acknowledged unregistered shutdown hook '{name}'");
+        }
+    }
+
+    /// Names of registered hooks that have not been acknowledged
+    pub fn pending(&self) -> impl Iterator<Item = &str> {
+        self.registered
+            .difference(&self.acknowledged)
+            .map(String::as_str)
+    }
+
+    fn reset(&mut self) {
+        self.acknowledged.clear();
+    }
+}
+
+/// Request parameters for `brp_extras/shutdown`
+#[derive(Debug, Default, Deserialize)]
+pub struct ShutdownRequest {
+    /// Process exit code, where 0 exits successfully and anything else exits with an error
+    #[serde(default)]
+    pub exit_code:       u8,
+    /// Frames to wait before exiting
+    #[serde(default)]
+    pub delay_frames:    Option<u32>,
+    /// Milliseconds to wait before exiting
+    #[serde(default)]
+    pub delay_ms:        Option<u64>,
+    /// Reason logged when the app exits
+    #[serde(default)]
+    pub reason:          Option<String>,
+    /// Milliseconds to wait for shutdown hooks before exiting anyway
+    #[serde(default)]
+    pub hook_timeout_ms: Option<u64>,
+}
+
 /// Resource to track pending shutdown
 #[derive(Resource)]
 pub struct PendingShutdown {
     frames_remaining: u32,
+    exit_at:          Option<Instant>,
+    deadline:         Instant,
+    exit:             AppExit,
+    reason:           Option<String>,
+    waiting_logged:   bool,
 }
 
 /// Handler for shutdown requests
 ///
-/// Schedules a graceful shutdown after a few frames to allow the response to be sent
-#[allow(clippy::unnecessary_wraps)]
-pub fn handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+/// Schedules a graceful shutdown after a delay to allow the response to be sent, and sends
+/// [`BrpShutdownRequested`] so app code can finish its work. A new request replaces any shutdown
+/// that is already pending.
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
     info!("BRP EXTRAS SHUTDOWN METHOD CALLED - scheduling deferred shutdown");
-    info!("Call stack: {:?}", std::backtrace::Backtrace::capture());
 
-    // Schedule shutdown for 10 frames from now (about 167ms at 60fps)
+    let request: ShutdownRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid shutdown parameters: {e}"),
+            data:    None,
+        })?,
+        None => ShutdownRequest::default(),
+    };
+
+    let (frames_remaining, exit_at, delay) = match (request.delay_frames, request.delay_ms) {
+        (Some(_), Some(_)) => {
+            return Err(BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: "Specify either 'delay_frames' or 'delay_ms', not both".to_string(),
+                data:    None,
+            });
+        }
+        (None, Some(delay_ms)) if delay_ms > MAX_DELAY_MS => {
+            return Err(BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!(
+                    "delay_ms {delay_ms} exceeds maximum allowed delay of {MAX_DELAY_MS}ms"
+                ),
+                data:    None,
+            });
+        }
+        (None, Some(delay_ms)) => (
+            MIN_DELAY_FRAMES,
+            Some(Instant::now() + Duration::from_millis(delay_ms)),
+            format!("{delay_ms}ms"),
+        ),
+        (delay_frames, None) => {
+            let frames = delay_frames
+                .unwrap_or(DEFAULT_DELAY_FRAMES)
+                .max(MIN_DELAY_FRAMES);
+            (frames, None, format!("{frames} frames"))
+        }
+    };
+
+    let hook_timeout_ms = request.hook_timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS);
+    if hook_timeout_ms > MAX_DELAY_MS {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!(
+                "hook_timeout_ms {hook_timeout_ms} exceeds maximum allowed timeout of \
+                 {MAX_DELAY_MS}ms"
+            ),
+            data:    None,
+        });
+    }
+    let hook_deadline = Instant::now() + Duration::from_millis(hook_timeout_ms);
+    let deadline = exit_at.map_or(hook_deadline, |exit_at| exit_at.max(hook_deadline));
+
+    if world.contains_resource::<PendingShutdown>() {
+        info!("Replacing pending shutdown");
+    }
     world.insert_resource(PendingShutdown {
-        frames_remaining: 10,
+        frames_remaining,
+        exit_at,
+        deadline,
+        exit: AppExit::from_code(request.exit_code),
+        reason: request.reason.clone(),
+        waiting_logged: false,
+    });
+
+    let mut hooks = world.get_resource_or_init::<ShutdownHooks>();
+    hooks.reset();
+    let hooks: Vec<String> = hooks.pending().map(str::to_string).collect();
+    world.send_event(BrpShutdownRequested {
+        reason: request.reason.clone(),
+        exit_code: request.exit_code,
+        deadline,
     });
 
-    info!("Shutdown scheduled - will exit in 10 frames");
+    info!(
+        "Shutdown scheduled - will exit with code {} in {delay}",
+        request.exit_code
+    );
 
     Ok(json!({
         "success": true,
-        "message": "Shutdown initiated - will exit in 10 frames"
+        "message": format!("Shutdown initiated - will exit in {delay}"),
+        "exit_code": request.exit_code,
+        "reason": request.reason,
+        "hooks": hooks
+    }))
+}
+
+/// Handler for cancelling a pending shutdown
+#[allow(clippy::unnecessary_wraps)]
+pub fn cancel_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let cancelled = world.remove_resource::<PendingShutdown>().is_some();
+    if let Some(mut hooks) = world.get_resource_mut::<ShutdownHooks>() {
+        hooks.reset();
+    }
+    let message = if cancelled {
+        info!("Pending shutdown cancelled");
+        "Pending shutdown cancelled"
+    } else {
+        "No shutdown was pending"
+    };
+
+    Ok(json!({
+        "success": true,
+        "cancelled": cancelled,
+        "message": message
     }))
 }
 
 /// System to handle deferred shutdown
+#[allow(clippy::needless_pass_by_value)]
 pub fn deferred_shutdown_system(
+    mut commands: Commands,
     pending: Option<ResMut<PendingShutdown>>,
-    mut exit: EventWriter<bevy::app::AppExit>,
+    hooks: Option<Res<ShutdownHooks>>,
+    mut exit: EventWriter<AppExit>,
 ) {
-    if let Some(mut shutdown) = pending {
-        shutdown.frames_remaining = shutdown.frames_remaining.saturating_sub(1);
+    let Some(mut shutdown) = pending else {
+        return;
+    };
+
+    shutdown.frames_remaining = shutdown.frames_remaining.saturating_sub(1);
 
-        if shutdown.frames_remaining == 0 {
-            info!("Deferred shutdown triggered - sending AppExit::Success event");
-            exit.write(bevy::app::AppExit::Success);
+    let now = Instant::now();
+    let delay_elapsed = shutdown.exit_at.is_none_or(|exit_at| now >= exit_at);
+    if shutdown.frames_remaining > 0 || !delay_elapsed {
+        return;
+    }
+
+    let pending_hooks: Vec<&str> = hooks
+        .as_ref()
+        .map(|hooks| hooks.pending().collect())
+        .unwrap_or_default();
+    if !pending_hooks.is_empty() {
+        if now < shutdown.deadline {
+            if !shutdown.waiting_logged {
+                info!("Shutdown waiting for hooks: {}", pending_hooks.join(", "));
+                shutdown.waiting_logged = true;
+            }
+            return;
         }
+        warn!(
+            "Shutdown deadline passed before hooks acknowledged: {}",
+            pending_hooks.join(", ")
+        );
+    }
+
+    let reason = shutdown
+        .reason
+        .as_ref()
+        .map_or_else(String::new, |reason| format!(": {reason}"));
+    info!(
+        "Deferred shutdown triggered - sending {:?} event{reason}",
+        shutdown.exit
+    );
+    exit.write(shutdown.exit.clone());
+    commands.remove_resource::<PendingShutdown>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn exit_events(world: &World) -> Vec<AppExit> {
+        let events = world.resource::<Events<AppExit>>();
+        events.get_cursor().read(events).cloned().collect()
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_shutdown_with_exit_code_after_delay() {
+        let mut world = World::new();
+        world.init_resource::<Events<AppExit>>();
+        world.init_resource::<Events<BrpShutdownRequested>>();
+
+        let params = json!({ "exit_code": 3, "delay_frames": 4, "reason": "tests failed" });
+        handler(In(Some(params)), &mut world).expect("Expected shutdown to be scheduled");
+
+        for _ in 0..3 {
+            world
+                .run_system_once(deferred_shutdown_system)
+                .expect("Expected system to run");
+        }
+        assert!(exit_events(&world).is_empty());
+
+        world
+            .run_system_once(deferred_shutdown_system)
+            .expect("Expected system to run");
+        let code = std::num::NonZeroU8::new(3).expect("Expected non-zero code");
+        assert_eq!(exit_events(&world), vec![AppExit::Error(code)]);
+        assert!(!world.contains_resource::<PendingShutdown>());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_cancel_shutdown() {
+        let mut world = World::new();
+        world.init_resource::<Events<AppExit>>();
+        world.init_resource::<Events<BrpShutdownRequested>>();
+
+        handler(In(Some(json!({ "delay_ms": 0 }))), &mut world).expect("Expected shutdown");
+        let response = cancel_handler(In(None), &mut world).expect("Expected cancel");
+        assert_eq!(response["cancelled"], true);
+
+        for _ in 0..MIN_DELAY_FRAMES {
+            world
+                .run_system_once(deferred_shutdown_system)
+                .expect("Expected system to run");
+        }
+        assert!(exit_events(&world).is_empty());
+
+        let response = cancel_handler(In(None), &mut world).expect("Expected cancel");
+        assert_eq!(response["cancelled"], false);
+        assert!(
+            handler(
+                In(Some(json!({ "delay_ms": 10, "delay_frames": 1 }))),
+                &mut world
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_shutdown_waits_for_hooks() {
+        let mut world = World::new();
+        world.init_resource::<Events<AppExit>>();
+        world.init_resource::<Events<BrpShutdownRequested>>();
+        let mut hooks = ShutdownHooks::default();
+        hooks.register("save_files");
+        world.insert_resource(hooks);
+
+        let params = json!({ "delay_frames": 3, "reason": "done", "hook_timeout_ms": 60_000 });
+        let response = handler(In(Some(params)), &mut world).expect("Expected shutdown");
+        assert_eq!(response["hooks"], json!(["save_files"]));
+        let events = world.resource::<Events<BrpShutdownRequested>>();
+        let requested: Vec<_> = events.get_cursor().read(events).cloned().collect();
+        assert_eq!(requested.len(), 1);
+        assert_eq!(requested[0].reason.as_deref(), Some("done"));
+
+        for _ in 0..5 {
+            world
+                .run_system_once(deferred_shutdown_system)
+                .expect("Expected system to run");
+        }
+        assert!(exit_events(&world).is_empty());
+
+        world
+            .resource_mut::<ShutdownHooks>()
+            .acknowledge("save_files");
+        world
+            .run_system_once(deferred_shutdown_system)
+            .expect("Expected system to run");
+        assert_eq!(exit_events(&world), vec![AppExit::Success]);
     }
 }
@@ -0,0 +1,268 @@
+//! Inspecting and changing `States` over BRP
+//!
+//! States are found through the type registry, so only state types registered with
+//! `App::register_type_state` or `App::register_type_mutable_state` are visible. `States` and
+//! `SubStates` registered as mutable can be changed by writing `NextState<S>`, while
+//! `ComputedStates` are read-only.
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::{ReflectFromReflect, TypeInfo, TypeRegistry};
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::state::reflect::{ReflectFreelyMutableState, ReflectState};
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::reflection::{app_type_registry, registration, to_json};
+
+/// A registered state type and its current value
+#[derive(Debug, Clone, Serialize)]
+pub struct StateInfo {
+    /// Full type path of the state type
+    pub type_path: String,
+    /// Short type name
+    pub name:      String,
+    /// Current value, or `None` when the state doesn't exist, such as an inactive sub state
+    pub value:     Option<Value>,
+    /// Whether the state can be set with `brp_extras/set_state`, which is false for computed
+    /// states
+    pub mutable:   bool,
+    /// Variant names when the state is an enum
+    pub variants:  Vec<String>,
+}
+
+/// Request parameters for `brp_extras/set_state`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetStateRequest {
+    /// Full type path of the state type
+    pub state: String,
+    /// New value, such as `"InGame"` for a unit variant or `{ "Level": 2 }` for a tuple variant
+    pub value: Value,
+}
+
+/// Handler for listing registered states and their current values
+#[allow(clippy::needless_pass_by_ref_mut)]
+pub fn list_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let type_registry = app_type_registry(world)?;
+    let states = list_states(world, &type_registry.read())?;
+
+    Ok(json!({
+        "success": true,
+        "states": states
+    }))
+}
+
+/// Collect every state type with reflected `State` data
+fn list_states(world: &World, registry: &TypeRegistry) -> Result<Vec<StateInfo>, BrpError> {
+    let mut states = Vec::new();
+    for registration in registry.iter() {
+        let Some(reflect_state) = registration.data::<ReflectState>() else {
+            continue;
+        };
+        let value = reflect_state
+            .reflect(world)
+            .map(|state| to_json(state.as_partial_reflect(), registry))
+            .transpose()?;
+        let variants = match registration.type_info() {
+            TypeInfo::Enum(info) => info
+                .variant_names()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+        states.push(StateInfo {
+            type_path: registration.type_info().type_path().to_string(),
+            name: registration
+                .type_info()
+                .type_path_table()
+                .short_path()
+                .to_string(),
+            value,
+            mutable: registration.contains::<ReflectFreelyMutableState>(),
+            variants,
+        });
+    }
+    states.sort_by(|a, b| a.type_path.cmp(&b.type_path));
+    Ok(states)
+}
+
+/// Handler for requesting a state transition by writing `NextState<S>`
+///
+/// The transition is applied the next time the `StateTransition` schedule runs.
+pub fn set_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: SetStateRequest = params
+        .ok_or_else(|| invalid("Missing parameters, expected 'state' and 'value'".to_string()))
+        .and_then(|params| {
+            serde_json::from_value(params)
+                .map_err(|e| invalid(format!("Invalid set_state parameters: {e}")))
+        })?;
+
+    let type_registry = app_type_registry(world)?;
+    let previous = set_state(world, &type_registry.read(), &request)?;
+    info!(
+        "Requested transition of {} from {previous} to {}",
+        request.state, request.value
+    );
+
+    Ok(json!({
+        "success": true,
+        "state": request.state,
+        "previous": previous,
+        "requested": request.value,
+        "message": "Transition queued, it applies when the StateTransition schedule next runs"
+    }))
+}
+
+/// Write `NextState<S>` for the requested state, returning the current value
+fn set_state(
+    world: &mut World,
+    registry: &TypeRegistry,
+    request: &SetStateRequest,
+) -> Result<Value, BrpError> {
+    let state_registration = registration(registry, &request.state)?;
+
+    let reflect_state = state_registration.data::<ReflectState>().ok_or_else(|| {
+        invalid(format!(
+            "'{}' is not a reflected state, register it with App::register_type_state",
+            request.state
+        ))
+    })?;
+    let reflect_mutable = state_registration
+        .data::<ReflectFreelyMutableState>()
+        .ok_or_else(|| {
+            invalid(format!(
+                "'{}' can't be set. Computed states can't be set, and other states must be \
+                 registered with App::register_type_mutable_state",
+                request.state
+            ))
+        })?;
+
+    let previous = reflect_state
+        .reflect(world)
+        .map(|state| to_json(state.as_partial_reflect(), registry))
+        .transpose()?
+        .ok_or_else(|| {
+            invalid(format!(
+                "State '{}' doesn't currently exist, so it can't be set",
+                request.state
+            ))
+        })?;
+
+    let new_state = deserialize_state(registry, request)?;
+    reflect_mutable.set_next_state(world, new_state.as_ref(), registry);
+    Ok(previous)
+}
+
+/// Build a state value from its JSON representation
+fn deserialize_state(
+    registry: &TypeRegistry,
+    request: &SetStateRequest,
+) -> Result<Box<dyn Reflect>, BrpError> {
+    let state_registration = registration(registry, &request.state)?;
+    let partial = TypedReflectDeserializer::new(state_registration, registry)
+        .deserialize(&request.value)
+        .map_err(|e| invalid(format!("Invalid value for {}: {e}", request.state)))?;
+
+    state_registration
+        .data::<ReflectFromReflect>()
+        .and_then(|from_reflect| from_reflect.from_reflect(partial.as_ref()))
+        .ok_or_else(|| {
+            invalid(format!(
+                "Could not build {} from {}",
+                request.state, request.value
+            ))
+        })
+}
+
+const fn invalid(message: String) -> BrpError {
+    BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+
+    #[derive(States, Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    enum GameState {
+        #[default]
+        Menu,
+        InGame,
+    }
+
+    #[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct InMenu;
+
+    impl ComputedStates for InMenu {
+        type SourceStates = GameState;
+
+        fn compute(sources: GameState) -> Option<Self> {
+            (sources == GameState::Menu).then_some(Self)
+        }
+    }
+
+    fn state_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_state::<GameState>()
+            .add_computed_state::<InMenu>()
+            .register_type_mutable_state::<GameState>()
+            .register_type_state::<InMenu>();
+        app.update();
+        app
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_list_states() {
+        let mut app = state_app();
+        let response = list_handler(In(None), app.world_mut()).expect("Expected states");
+        let states: Vec<&Value> = response["states"]
+            .as_array()
+            .expect("Expected a list")
+            .iter()
+            .collect();
+
+        let game_state = states
+            .iter()
+            .find(|state| state["name"] == "GameState")
+            .expect("Expected GameState");
+        assert_eq!(game_state["value"], "Menu");
+        assert_eq!(game_state["mutable"], true);
+        assert_eq!(game_state["variants"], json!(["Menu", "InGame"]));
+
+        let in_menu = states
+            .iter()
+            .find(|state| state["name"] == "InMenu")
+            .expect("Expected InMenu");
+        assert_eq!(in_menu["mutable"], false);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_set_state() {
+        let mut app = state_app();
+        let params = json!({ "state": std::any::type_name::<GameState>(), "value": "InGame" });
+        let response = set_handler(In(Some(params)), app.world_mut()).expect("Expected success");
+        assert_eq!(response["previous"], "Menu");
+
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<GameState>>().get(),
+            GameState::InGame
+        );
+        assert!(!app.world().contains_resource::<State<InMenu>>());
+
+        let params = json!({ "state": std::any::type_name::<GameState>(), "value": "Missing" });
+        assert!(set_handler(In(Some(params)), app.world_mut()).is_err());
+        let params = json!({ "state": std::any::type_name::<InMenu>(), "value": null });
+        assert!(set_handler(In(Some(params)), app.world_mut()).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+//! Pausing, resuming and single-stepping the app over BRP
+//!
+//! Pausing stops `Time<Virtual>`, which freezes gameplay that follows virtual time and the fixed
+//! timestep loop while frames keep running, so BRP requests are still serviced. Stepping a number
+//! of frames unpauses virtual time for exactly that many frames. Stepping fixed ticks runs
+//! `FixedMain` once per frame with virtual time kept paused, so exactly that many ticks run.
+//!
+//! The relative speed and maximum delta of `Time<Virtual>` and the `Time<Fixed>` timestep can be
+//! read and changed with `brp_extras/time_settings`.
+
+use std::time::Duration;
+
+use bevy::app::FixedMain;
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Maximum number of frames or fixed ticks in a single step (1 minute at 60 fps)
+const MAX_STEP_COUNT: u32 = 3_600;
+
+/// Request parameters for `brp_extras/step`
+#[derive(Debug, Default, Deserialize)]
+pub struct StepRequest {
+    /// Number of frames to advance virtual time by before pausing again
+    #[serde(default)]
+    pub frames:      Option<u32>,
+    /// Number of fixed timestep ticks to run before pausing again
+    #[serde(default)]
+    pub fixed_ticks: Option<u32>,
+}
+
+/// Request parameters for `brp_extras/time_settings`
+///
+/// Settings that are omitted keep their current value.
+#[derive(Debug, Default, Deserialize)]
+pub struct TimeSettingsRequest {
+    /// Speed of virtual time relative to real time, such as 10.0 or 0.25
+    #[serde(default)]
+    pub relative_speed:      Option<f64>,
+    /// Maximum real time virtual time advances by in a single frame, in seconds
+    #[serde(default)]
+    pub max_delta_secs:      Option<f64>,
+    /// Fixed timestep in seconds
+    #[serde(default)]
+    pub fixed_timestep_secs: Option<f64>,
+}
+
+/// Time settings reported by `brp_extras/time_settings`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimeSettings {
+    /// Speed of virtual time relative to real time
+    pub relative_speed:      f64,
+    /// Maximum real time virtual time advances by in a single frame, in seconds
+    pub max_delta_secs:      f64,
+    /// Fixed timestep in seconds
+    pub fixed_timestep_secs: f64,
+    /// Whether virtual time is paused
+    pub paused:              bool,
+}
+
+/// Response for `brp_extras/time_settings`
+#[derive(Debug, Serialize)]
+pub struct TimeSettingsResponse {
+    /// Whether the request succeeded
+    pub success:  bool,
+    /// Settings before the request was applied
+    pub previous: TimeSettings,
+    /// Settings after the request was applied
+    pub current:  TimeSettings,
+}
+
+/// Resource tracking a step in progress
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingStep {
+    /// Frames left to run with virtual time unpaused
+    Frames(u32),
+    /// Fixed timestep ticks left to run
+    FixedTicks(u32),
+}
+
+/// Handler for pausing `Time<Virtual>`
+pub fn pause_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let was_paused = set_paused(world, true)?;
+    world.remove_resource::<PendingStep>();
+    info!("Paused virtual time");
+
+    Ok(json!({
+        "success": true,
+        "paused": true,
+        "was_paused": was_paused,
+        "elapsed_secs": virtual_elapsed_secs(world)
+    }))
+}
+
+/// Handler for resuming `Time<Virtual>`, cancelling any step in progress
+pub fn resume_handler(In(_): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let was_paused = set_paused(world, false)?;
+    world.remove_resource::<PendingStep>();
+    info!("Resumed virtual time");
+
+    Ok(json!({
+        "success": true,
+        "paused": false,
+        "was_paused": was_paused,
+        "elapsed_secs": virtual_elapsed_secs(world)
+    }))
+}
+
+/// Handler for advancing a number of frames or fixed ticks and then pausing
+///
+/// Responds immediately, the step runs over the following frames. Without parameters a single
+/// frame is stepped.
+pub fn step_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: StepRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid step parameters: {e}"),
+            data:    None,
+        })?,
+        None => StepRequest::default(),
+    };
+
+    let step = match (request.frames, request.fixed_ticks) {
+        (Some(_), Some(_)) => {
+            return Err(invalid_params(
+                "Specify either 'frames' or 'fixed_ticks', not both".to_string(),
+            ));
+        }
+        (frames, None) => PendingStep::Frames(frames.unwrap_or(1)),
+        (None, Some(ticks)) => PendingStep::FixedTicks(ticks),
+    };
+    let (PendingStep::Frames(count) | PendingStep::FixedTicks(count)) = step;
+    if count == 0 || count > MAX_STEP_COUNT {
+        return Err(invalid_params(format!(
+            "Step count must be between 1 and {MAX_STEP_COUNT}, got {count}"
+        )));
+    }
+
+    // Frame steps run with virtual time unpaused, tick steps run fixed ticks while paused
+    set_paused(world, matches!(step, PendingStep::FixedTicks(_)))?;
+    world.insert_resource(step);
+
+    let (unit, field) = match step {
+        PendingStep::Frames(_) => ("frames", "frames"),
+        PendingStep::FixedTicks(_) => ("fixed ticks", "fixed_ticks"),
+    };
+    let message = format!("Stepping {count} {unit}, then pausing");
+    info!("{message}");
+
+    Ok(json!({
+        "success": true,
+        field: count,
+        "message": message
+    }))
+}
+
+/// System that pauses virtual time once a frame step has run its frames
+pub fn process_frame_step(
+    mut commands: Commands,
+    step: Option<ResMut<PendingStep>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    let Some(mut step) = step else {
+        return;
+    };
+    let PendingStep::Frames(remaining) = step.as_mut() else {
+        return;
+    };
+
+    *remaining = remaining.saturating_sub(1);
+    if *remaining == 0 {
+        virtual_time.pause();
+        commands.remove_resource::<PendingStep>();
+        debug!("Frame step finished, virtual time paused");
+    }
+}
+
+/// System that runs one fixed timestep tick per frame while a tick step is in progress
+pub fn process_fixed_step(world: &mut World) {
+    let Some(PendingStep::FixedTicks(remaining)) = world.get_resource::<PendingStep>().copied()
+    else {
+        return;
+    };
+
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+    let _ = world.try_run_schedule(FixedMain);
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+
+    if remaining > 1 {
+        world.insert_resource(PendingStep::FixedTicks(remaining - 1));
+    } else {
+        world.remove_resource::<PendingStep>();
+        debug!("Fixed tick step finished");
+    }
+}
+
+/// Handler for reading and changing the virtual time speed and fixed timestep
+///
+/// Returns the previous and current settings. Without parameters the settings are only read.
+pub fn time_settings_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: TimeSettingsRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid time settings parameters: {e}"),
+            data:    None,
+        })?,
+        None => TimeSettingsRequest::default(),
+    };
+
+    if let Some(speed) = request.relative_speed
+        && !(speed.is_finite() && speed >= 0.0)
+    {
+        return Err(invalid_params(format!(
+            "relative_speed must be a finite number of at least 0, got {speed}"
+        )));
+    }
+    let max_delta = request
+        .max_delta_secs
+        .map(|secs| positive_duration("max_delta_secs", secs))
+        .transpose()?;
+    let fixed_timestep = request
+        .fixed_timestep_secs
+        .map(|secs| positive_duration("fixed_timestep_secs", secs))
+        .transpose()?;
+
+    let previous = current_time_settings(world)?;
+
+    {
+        let mut virtual_time = world.resource_mut::<Time<Virtual>>();
+        if let Some(speed) = request.relative_speed {
+            virtual_time.set_relative_speed_f64(speed);
+        }
+        if let Some(max_delta) = max_delta {
+            virtual_time.set_max_delta(max_delta);
+        }
+    }
+    if let Some(timestep) = fixed_timestep {
+        world.resource_mut::<Time<Fixed>>().set_timestep(timestep);
+    }
+
+    let current = current_time_settings(world)?;
+    if current != previous {
+        info!(
+            "Time settings changed - relative speed {}, max delta {}s, fixed timestep {}s",
+            current.relative_speed, current.max_delta_secs, current.fixed_timestep_secs
+        );
+    }
+
+    serde_json::to_value(TimeSettingsResponse {
+        success: true,
+        previous,
+        current,
+    })
+    .map_err(|e| BrpError {
+        code:    error_codes::INTERNAL_ERROR,
+        message: format!("Failed to serialize response: {e}"),
+        data:    None,
+    })
+}
+
+/// Read the current time settings, failing when the time resources are missing
+fn current_time_settings(world: &World) -> Result<TimeSettings, BrpError> {
+    let (Some(virtual_time), Some(fixed_time)) = (
+        world.get_resource::<Time<Virtual>>(),
+        world.get_resource::<Time<Fixed>>(),
+    ) else {
+        return Err(time_unavailable());
+    };
+
+    Ok(TimeSettings {
+        relative_speed:      virtual_time.relative_speed_f64(),
+        max_delta_secs:      virtual_time.max_delta().as_secs_f64(),
+        fixed_timestep_secs: fixed_time.timestep().as_secs_f64(),
+        paused:              virtual_time.is_paused(),
+    })
+}
+
+/// Convert a number of seconds to a non-zero duration
+fn positive_duration(name: &str, secs: f64) -> Result<Duration, BrpError> {
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| invalid_params(format!("{name} must be a positive number, got {secs}")))
+}
+
+/// Pause or unpause virtual time, returning whether it was paused before
+fn set_paused(world: &mut World, paused: bool) -> Result<bool, BrpError> {
+    let mut virtual_time = world
+        .get_resource_mut::<Time<Virtual>>()
+        .ok_or_else(time_unavailable)?;
+
+    let was_paused = virtual_time.is_paused();
+    if paused {
+        virtual_time.pause();
+    } else {
+        virtual_time.unpause();
+    }
+    Ok(was_paused)
+}
+
+fn virtual_elapsed_secs(world: &World) -> Option<f64> {
+    world
+        .get_resource::<Time<Virtual>>()
+        .map(Time::elapsed_secs_f64)
+}
+
+fn time_unavailable() -> BrpError {
+    BrpError {
+        code:    error_codes::INTERNAL_ERROR,
+        message: "Time<Virtual> is not available, add TimePlugin to the app".to_string(),
+        data:    None,
+    }
+}
+
+const fn invalid_params(message: String) -> BrpError {
+    BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct TickCount(u32);
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_time_settings_return_previous_values() {
+        let mut world = time_world();
+        world.insert_resource(Time::<Fixed>::from_seconds(0.5));
+
+        let params =
+            json!({ "relative_speed": 10.0, "max_delta_secs": 0.1, "fixed_timestep_secs": 0.25 });
+        let response =
+            time_settings_handler(In(Some(params)), &mut world).expect("Expected success");
+        assert_eq!(response["previous"]["relative_speed"], 1.0);
+        assert_eq!(response["previous"]["fixed_timestep_secs"], 0.5);
+        assert_eq!(response["current"]["relative_speed"], 10.0);
+        assert_eq!(
+            world.resource::<Time<Virtual>>().max_delta(),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            world.resource::<Time<Fixed>>().timestep(),
+            Duration::from_millis(250)
+        );
+
+        let response = time_settings_handler(In(None), &mut world).expect("Expected read");
+        assert_eq!(response["previous"], response["current"]);
+
+        for params in [
+            json!({ "relative_speed": -1.0 }),
+            json!({ "max_delta_secs": 0.0 }),
+            json!({ "fixed_timestep_secs": -0.5 }),
+        ] {
+            assert!(time_settings_handler(In(Some(params)), &mut world).is_err());
+        }
+        let speed = world.resource::<Time<Virtual>>().relative_speed_f64();
+        assert!((speed - 10.0).abs() < f64::EPSILON);
+    }
+
+    fn time_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Time<Virtual>>();
+        world.init_resource::<Time<Fixed>>();
+        world
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_pause_resume_and_step_frames() {
+        let mut world = time_world();
+
+        let response = pause_handler(In(None), &mut world).expect("Expected pause");
+        assert_eq!(response["was_paused"], false);
+        assert!(world.resource::<Time<Virtual>>().is_paused());
+
+        step_handler(In(Some(json!({ "frames": 2 }))), &mut world).expect("Expected step");
+        assert!(!world.resource::<Time<Virtual>>().is_paused());
+        for _ in 0..2 {
+            world
+                .run_system_once(process_frame_step)
+                .expect("Expected system to run");
+        }
+        assert!(world.resource::<Time<Virtual>>().is_paused());
+        assert!(!world.contains_resource::<PendingStep>());
+
+        let response = resume_handler(In(None), &mut world).expect("Expected resume");
+        assert_eq!(response["was_paused"], true);
+        assert!(!world.resource::<Time<Virtual>>().is_paused());
+
+        assert!(step_handler(In(Some(json!({ "frames": 0 }))), &mut world).is_err());
+        assert!(
+            step_handler(
+                In(Some(json!({ "frames": 1, "fixed_ticks": 1 }))),
+                &mut world
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_step_fixed_ticks() {
+        let mut world = time_world();
+        world.insert_resource(Time::<Fixed>::from_seconds(0.25));
+        world.init_resource::<TickCount>();
+        let mut schedule = Schedule::new(FixedMain);
+        schedule.add_systems(|mut count: ResMut<TickCount>| count.0 += 1);
+        world.add_schedule(schedule);
+
+        step_handler(In(Some(json!({ "fixed_ticks": 3 }))), &mut world).expect("Expected step");
+        assert!(world.resource::<Time<Virtual>>().is_paused());
+        for _ in 0..5 {
+            process_fixed_step(&mut world);
+        }
+
+        assert_eq!(world.resource::<TickCount>().0, 3);
+        assert_eq!(
+            world.resource::<Time<Fixed>>().elapsed(),
+            Duration::from_millis(750)
+        );
+        assert_eq!(world.resource::<Time>().elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,147 @@
+//! Timing for injected input
+//!
+//! Injected input can be timed in milliseconds on either `Time<Virtual>` or `Time<Real>`, or in
+//! frames. Frame timing doesn't depend on frame rate, so it gives reproducible input in tests
+//! that run with `MinimalPlugins` or a manual `TimeUpdateStrategy`.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::remote::{BrpError, error_codes};
+use serde::{Deserialize, Serialize};
+
+/// Maximum duration for injected input in milliseconds (1 minute)
+pub const MAX_INPUT_DURATION_MS: u32 = 60_000;
+
+/// Maximum duration for injected input in frames (1 minute at 60 fps)
+pub const MAX_INPUT_DURATION_FRAMES: u32 = 3_600;
+
+/// Time source used for millisecond timing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeSource {
+    /// `Time<Virtual>`, which follows pausing and relative speed
+    #[default]
+    Virtual,
+    /// `Time<Real>`, which is unaffected by pausing and relative speed
+    Real,
+}
+
+/// Unit that an `InputClock` counts in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputClockMode {
+    /// Milliseconds measured on the given time source
+    Milliseconds(TimeSource),
+    /// Frames, counted once per update
+    Frames,
+}
+
+/// Clock that measures how long injected input has been active
+#[derive(Debug, Clone, Copy)]
+pub struct InputClock {
+    /// Unit the clock counts in
+    pub mode:    InputClockMode,
+    /// Time elapsed on the selected time source
+    pub elapsed: Duration,
+    /// Frames elapsed
+    pub frames:  u32,
+}
+
+impl InputClock {
+    /// Create a clock counting in the given unit
+    #[must_use]
+    pub const fn new(mode: InputClockMode) -> Self {
+        Self {
+            mode,
+            elapsed: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    /// Advance the clock by one frame
+    pub fn tick(&mut self, virtual_time: &Time<Virtual>, real_time: &Time<Real>) {
+        self.frames = self.frames.saturating_add(1);
+        self.elapsed += match self.mode {
+            InputClockMode::Milliseconds(TimeSource::Real) => real_time.delta(),
+            InputClockMode::Milliseconds(TimeSource::Virtual) | InputClockMode::Frames => {
+                virtual_time.delta()
+            }
+        };
+    }
+
+    /// Elapsed time in the clock's unit
+    #[must_use]
+    pub fn elapsed_units(&self) -> u32 {
+        match self.mode {
+            InputClockMode::Milliseconds(_) => {
+                u32::try_from(self.elapsed.as_millis()).unwrap_or(u32::MAX)
+            }
+            InputClockMode::Frames => self.frames,
+        }
+    }
+
+    /// Whether the clock has reached an offset given in the clock's unit
+    #[must_use]
+    pub fn has_reached(&self, offset: u32) -> bool {
+        self.elapsed_units() >= offset
+    }
+
+    /// Units left until the clock reaches an offset given in the clock's unit
+    #[must_use]
+    pub fn remaining(&self, offset: u32) -> u32 {
+        offset.saturating_sub(self.elapsed_units())
+    }
+}
+
+/// Resolve optional millisecond and frame durations into a clock mode and a duration
+///
+/// At most one of `duration_ms` and `duration_frames` may be given. When neither is given the
+/// default millisecond duration is used.
+pub fn resolve_duration(
+    duration_ms: Option<u32>,
+    duration_frames: Option<u32>,
+    default_ms: u32,
+    time_source: TimeSource,
+) -> Result<(InputClockMode, u32), BrpError> {
+    match (duration_ms, duration_frames) {
+        (Some(_), Some(_)) => Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Specify either 'duration_ms' or 'duration_frames', not both".to_string(),
+            data:    None,
+        }),
+        (_, Some(frames)) => {
+            validate_duration(InputClockMode::Frames, frames)?;
+            Ok((InputClockMode::Frames, frames))
+        }
+        (ms, None) => {
+            let mode = InputClockMode::Milliseconds(time_source);
+            let ms = ms.unwrap_or(default_ms);
+            validate_duration(mode, ms)?;
+            Ok((mode, ms))
+        }
+    }
+}
+
+/// Validate a duration in the given unit doesn't exceed the maximum
+pub fn validate_duration(mode: InputClockMode, duration: u32) -> Result<(), BrpError> {
+    match mode {
+        InputClockMode::Milliseconds(_) if duration > MAX_INPUT_DURATION_MS => Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!(
+                "Duration {duration}ms exceeds maximum allowed duration of \
+                 {MAX_INPUT_DURATION_MS}ms (1 minute)"
+            ),
+            data:    None,
+        }),
+        InputClockMode::Frames if duration > MAX_INPUT_DURATION_FRAMES => Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!(
+                "Duration {duration} frames exceeds maximum allowed duration of \
+                 {MAX_INPUT_DURATION_FRAMES} frames"
+            ),
+            data:    None,
+        }),
+        _ => Ok(()),
+    }
+}
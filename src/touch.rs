@@ -0,0 +1,597 @@
+//! Touch and gesture input simulation for BRP extras
+
+use std::collections::VecDeque;
+
+use bevy::input::touch::{TouchInput, TouchPhase, Touches};
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::timing::{InputClock, InputClockMode, TimeSource, resolve_duration, validate_duration};
+
+/// Maximum number of intermediate move events generated for a gesture
+const MAX_GESTURE_STEPS: u32 = 240;
+
+/// Default duration for a tap in milliseconds
+const DEFAULT_TAP_DURATION_MS: u32 = 50;
+
+/// Default duration for a long press in milliseconds
+const DEFAULT_LONG_PRESS_DURATION_MS: u32 = 800;
+
+/// Default duration for swipe and pinch gestures in milliseconds
+const DEFAULT_MOTION_DURATION_MS: u32 = 300;
+
+/// Default number of move events generated for swipe and pinch gestures
+const DEFAULT_GESTURE_STEPS: u32 = 10;
+
+/// A touch event waiting to be sent
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduledTouch {
+    /// Offset from the start of the sequence when the event is sent, in the clock's unit
+    pub at:       u32,
+    /// The phase of the touch
+    pub phase:    TouchPhase,
+    /// The finger identifier
+    pub id:       u64,
+    /// Position of the touch in logical window coordinates
+    pub position: Vec2,
+}
+
+/// Component that tracks touch events that still need to be sent
+#[derive(Component)]
+pub struct TimedTouchSequence {
+    /// Touch events ordered by the time they are due
+    pub events: VecDeque<ScheduledTouch>,
+    /// Clock tracking how long the sequence has been running
+    pub clock:  InputClock,
+}
+
+/// A single touch event in a `send_touch` request
+#[derive(Debug, Clone, Deserialize)]
+pub struct TouchEventRequest {
+    /// Touch phase (`Started`, `Moved`, `Ended` or `Canceled`)
+    pub phase:     TouchPhase,
+    /// The finger identifier
+    #[serde(default)]
+    pub id:        u64,
+    /// Position of the touch in logical window coordinates
+    pub position:  Vec2,
+    /// Delay in milliseconds after the request before the event is sent
+    pub at_ms:     Option<u32>,
+    /// Delay in frames after the request before the event is sent, instead of `at_ms`
+    pub at_frames: Option<u32>,
+}
+
+/// Request structure for `send_touch`
+#[derive(Debug, Deserialize)]
+pub struct SendTouchRequest {
+    /// Touch events to send
+    pub events:      Vec<TouchEventRequest>,
+    /// Time source for `at_ms` delays
+    #[serde(default)]
+    pub time_source: TimeSource,
+}
+
+/// A touch gesture that can be expanded into a stream of touch events
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "gesture", rename_all = "snake_case")]
+pub enum Gesture {
+    /// Touch down and up at a single position
+    Tap {
+        /// Position of the tap
+        position: Vec2,
+        /// The finger identifier
+        #[serde(default)]
+        id:       u64,
+    },
+    /// Touch down at a position and hold before lifting
+    LongPress {
+        /// Position of the press
+        position: Vec2,
+        /// The finger identifier
+        #[serde(default)]
+        id:       u64,
+    },
+    /// Drag a single finger from one position to another
+    Swipe {
+        /// Start position
+        from:  Vec2,
+        /// End position
+        to:    Vec2,
+        /// The finger identifier
+        #[serde(default)]
+        id:    u64,
+        /// Number of move events between the start and end positions
+        #[serde(default = "default_gesture_steps")]
+        steps: u32,
+    },
+    /// Move two fingers toward or away from a center point
+    Pinch {
+        /// Center point between the two fingers
+        center:         Vec2,
+        /// Distance between the fingers at the start
+        start_distance: f32,
+        /// Distance between the fingers at the end
+        end_distance:   f32,
+        /// Angle of the line between the fingers in degrees
+        #[serde(default)]
+        angle_degrees:  f32,
+        /// Identifier of the first finger, the second uses `id + 1`
+        #[serde(default)]
+        id:             u64,
+        /// Number of move events between the start and end positions
+        #[serde(default = "default_gesture_steps")]
+        steps:          u32,
+    },
+}
+
+/// Request structure for `send_gesture`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GestureRequest {
+    /// The gesture to perform
+    #[serde(flatten)]
+    pub gesture:         Gesture,
+    /// How long the gesture takes in milliseconds
+    pub duration_ms:     Option<u32>,
+    /// How long the gesture takes in frames, instead of `duration_ms`
+    pub duration_frames: Option<u32>,
+    /// Time source for `duration_ms`
+    #[serde(default)]
+    pub time_source:     TimeSource,
+}
+
+const fn default_gesture_steps() -> u32 {
+    DEFAULT_GESTURE_STEPS
+}
+
+/// Response structure for `send_touch` and `send_gesture`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendTouchResponse {
+    /// Whether the operation was successful
+    pub success:          bool,
+    /// Number of touch events scheduled
+    pub events_scheduled: usize,
+    /// Time in milliseconds until the last event is sent, absent when frame timing was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms:      Option<u32>,
+    /// Frames until the last event is sent, if frame timing was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_frames:  Option<u32>,
+}
+
+impl Gesture {
+    /// Default duration of the gesture in milliseconds
+    const fn default_duration_ms(&self) -> u32 {
+        match self {
+            Self::Tap { .. } => DEFAULT_TAP_DURATION_MS,
+            Self::LongPress { .. } => DEFAULT_LONG_PRESS_DURATION_MS,
+            Self::Swipe { .. } | Self::Pinch { .. } => DEFAULT_MOTION_DURATION_MS,
+        }
+    }
+
+    /// Expand the gesture into a stream of touch events spread over `duration` clock units
+    fn expand(&self, duration: u32) -> Vec<ScheduledTouch> {
+        match *self {
+            Self::Tap { position, id } | Self::LongPress { position, id } => vec![
+                ScheduledTouch {
+                    at: 0,
+                    phase: TouchPhase::Started,
+                    id,
+                    position,
+                },
+                ScheduledTouch {
+                    at: duration,
+                    phase: TouchPhase::Ended,
+                    id,
+                    position,
+                },
+            ],
+            Self::Swipe {
+                from,
+                to,
+                id,
+                steps,
+            } => finger_path(id, from, to, duration, steps),
+            Self::Pinch {
+                center,
+                start_distance,
+                end_distance,
+                angle_degrees,
+                id,
+                steps,
+            } => {
+                let direction = Vec2::from_angle(angle_degrees.to_radians());
+                let start_offset = direction * start_distance / 2.0;
+                let end_offset = direction * end_distance / 2.0;
+
+                let mut events = finger_path(
+                    id,
+                    center - start_offset,
+                    center - end_offset,
+                    duration,
+                    steps,
+                );
+                events.extend(finger_path(
+                    id.wrapping_add(1),
+                    center + start_offset,
+                    center + end_offset,
+                    duration,
+                    steps,
+                ));
+                events.sort_by_key(|event| event.at);
+                events
+            }
+        }
+    }
+}
+
+/// Generate the events for a single finger moving in a straight line
+fn finger_path(id: u64, from: Vec2, to: Vec2, duration: u32, steps: u32) -> Vec<ScheduledTouch> {
+    let mut events = vec![ScheduledTouch {
+        at: 0,
+        phase: TouchPhase::Started,
+        id,
+        position: from,
+    }];
+
+    for step in 1..=steps {
+        let at = u64::from(duration) * u64::from(step) / u64::from(steps);
+        #[allow(clippy::cast_precision_loss)]
+        let t = step as f32 / steps as f32;
+        events.push(ScheduledTouch {
+            at: u32::try_from(at).unwrap_or(duration),
+            phase: TouchPhase::Moved,
+            id,
+            position: from.lerp(to, t),
+        });
+    }
+
+    events.push(ScheduledTouch {
+        at: duration,
+        phase: TouchPhase::Ended,
+        id,
+        position: to,
+    });
+    events
+}
+
+/// Parse request parameters into the given request type
+fn parse_request<T: serde::de::DeserializeOwned>(params: Option<Value>) -> Result<T, BrpError> {
+    let Some(params) = params else {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Missing request parameters".to_string(),
+            data:    None,
+        });
+    };
+
+    serde_json::from_value(params).map_err(|e| BrpError {
+        code:    error_codes::INVALID_PARAMS,
+        message: format!("Invalid request format: {e}"),
+        data:    None,
+    })
+}
+
+/// Send events that are due immediately and spawn a sequence for the rest
+fn schedule_touch_events(
+    world: &mut World,
+    clock_mode: InputClockMode,
+    mut events: Vec<ScheduledTouch>,
+) -> SendTouchResponse {
+    events.sort_by_key(|event| event.at);
+    let events_scheduled = events.len();
+    let duration = events.last().map_or(0, |event| event.at);
+
+    let window = primary_window(world);
+    let mut events: VecDeque<ScheduledTouch> = events.into();
+    while let Some(event) = events.front().filter(|event| event.at == 0) {
+        world.send_event(touch_input(event, window));
+        events.pop_front();
+    }
+
+    if !events.is_empty() {
+        world.spawn(TimedTouchSequence {
+            events,
+            clock: InputClock::new(clock_mode),
+        });
+    }
+
+    let (duration_ms, duration_frames) = match clock_mode {
+        InputClockMode::Milliseconds(_) => (Some(duration), None),
+        InputClockMode::Frames => (None, Some(duration)),
+    };
+
+    SendTouchResponse {
+        success: true,
+        events_scheduled,
+        duration_ms,
+        duration_frames,
+    }
+}
+
+/// Get the primary window entity, falling back to a placeholder when there is no window
+fn primary_window(world: &mut World) -> Entity {
+    world
+        .query_filtered::<Entity, With<PrimaryWindow>>()
+        .iter(world)
+        .next()
+        .unwrap_or(Entity::PLACEHOLDER)
+}
+
+/// Remove every pending touch sequence, canceling the touches it left down
+///
+/// Returns the number of sequences removed
+pub fn cancel_touch_sequences(world: &mut World) -> usize {
+    let mut entities = Vec::new();
+    let mut held: Vec<ScheduledTouch> = Vec::new();
+    for (entity, sequence) in world.query::<(Entity, &TimedTouchSequence)>().iter(world) {
+        entities.push(entity);
+        // A finger is down if its next pending event continues a touch instead of starting one
+        let mut seen = Vec::new();
+        for event in &sequence.events {
+            if seen.contains(&event.id) {
+                continue;
+            }
+            seen.push(event.id);
+            if event.phase != TouchPhase::Started {
+                held.push(*event);
+            }
+        }
+    }
+
+    let window = primary_window(world);
+    for mut event in held {
+        if let Some(touch) = world
+            .get_resource::<Touches>()
+            .and_then(|touches| touches.get_pressed(event.id))
+        {
+            event.position = touch.position();
+        }
+        event.phase = TouchPhase::Canceled;
+        world.send_event(touch_input(&event, window));
+    }
+    for entity in &entities {
+        world.despawn(*entity);
+    }
+    entities.len()
+}
+
+/// Create a `TouchInput` event from a scheduled touch
+const fn touch_input(event: &ScheduledTouch, window: Entity) -> TouchInput {
+    TouchInput {
+        phase: event.phase,
+        position: event.position,
+        window,
+        force: None,
+        id: event.id,
+    }
+}
+
+/// Handler for `send_touch` requests
+///
+/// Sends raw touch events, optionally delayed, for one or more fingers
+///
+/// # Errors
+///
+/// Returns `BrpError` if:
+/// - Request parameters are missing
+/// - Request format is invalid
+/// - Delays mix `at_ms` and `at_frames`
+/// - Any event is delayed beyond the maximum duration
+pub fn send_touch_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: SendTouchRequest = parse_request(params)?;
+
+    let uses_frames = request.events.iter().any(|event| event.at_frames.is_some());
+    let uses_ms = request.events.iter().any(|event| event.at_ms.is_some());
+    if uses_frames && uses_ms {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Touch events must all use either 'at_ms' or 'at_frames', not both"
+                .to_string(),
+            data:    None,
+        });
+    }
+
+    let clock_mode = if uses_frames {
+        InputClockMode::Frames
+    } else {
+        InputClockMode::Milliseconds(request.time_source)
+    };
+
+    let events: Vec<ScheduledTouch> = request
+        .events
+        .iter()
+        .map(|event| ScheduledTouch {
+            at:       event.at_frames.or(event.at_ms).unwrap_or(0),
+            phase:    event.phase,
+            id:       event.id,
+            position: event.position,
+        })
+        .collect();
+
+    let max_delay = events.iter().map(|event| event.at).max().unwrap_or(0);
+    validate_duration(clock_mode, max_delay)?;
+
+    Ok(json!(schedule_touch_events(world, clock_mode, events)))
+}
+
+/// Handler for `send_gesture` requests
+///
+/// Expands a tap, long press, swipe or pinch gesture into a timed stream of touch events
+///
+/// # Errors
+///
+/// Returns `BrpError` if:
+/// - Request parameters are missing
+/// - Request format is invalid
+/// - Both `duration_ms` and `duration_frames` are given
+/// - The gesture duration or step count exceeds the maximum
+pub fn send_gesture_handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: GestureRequest = parse_request(params)?;
+    let (clock_mode, duration) = resolve_duration(
+        request.duration_ms,
+        request.duration_frames,
+        request.gesture.default_duration_ms(),
+        request.time_source,
+    )?;
+
+    if let Gesture::Swipe { steps, .. } | Gesture::Pinch { steps, .. } = request.gesture
+        && steps > MAX_GESTURE_STEPS
+    {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Steps {steps} exceeds maximum allowed steps of {MAX_GESTURE_STEPS}"),
+            data:    None,
+        });
+    }
+
+    let events = request.gesture.expand(duration);
+    Ok(json!(schedule_touch_events(world, clock_mode, events)))
+}
+
+/// System that sends scheduled touch events when they are due
+#[allow(clippy::needless_pass_by_value)]
+pub fn process_timed_touch_events(
+    mut commands: Commands,
+    virtual_time: Res<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    mut query: Query<(Entity, &mut TimedTouchSequence)>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut touch_events: EventWriter<TouchInput>,
+) {
+    let window = windows.iter().next().unwrap_or(Entity::PLACEHOLDER);
+
+    for (entity, mut sequence) in &mut query {
+        sequence.clock.tick(&virtual_time, &real_time);
+
+        while let Some(event) = sequence
+            .events
+            .front()
+            .copied()
+            .filter(|event| sequence.clock.has_reached(event.at))
+        {
+            touch_events.write(touch_input(&event, window));
+            sequence.events.pop_front();
+        }
+
+        if sequence.events.is_empty() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::InputPlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_tap_expands_to_start_and_end() {
+        let gesture = Gesture::Tap {
+            position: Vec2::new(10.0, 20.0),
+            id:       3,
+        };
+        let events = gesture.expand(50);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].phase, TouchPhase::Started);
+        assert_eq!(events[1].phase, TouchPhase::Ended);
+        assert_eq!(events[1].at, 50);
+        assert!(events.iter().all(|event| event.id == 3));
+    }
+
+    #[test]
+    fn test_pinch_uses_two_fingers() {
+        let gesture = Gesture::Pinch {
+            center:         Vec2::new(100.0, 100.0),
+            start_distance: 20.0,
+            end_distance:   200.0,
+            angle_degrees:  0.0,
+            id:             0,
+            steps:          4,
+        };
+        let events = gesture.expand(300);
+
+        // Each finger gets a start, four moves and an end
+        assert_eq!(events.len(), 12);
+        let last_left = events
+            .iter()
+            .rfind(|event| event.id == 0)
+            .map(|event| event.position);
+        let last_right = events
+            .iter()
+            .rfind(|event| event.id == 1)
+            .map(|event| event.position);
+        assert_eq!(last_left, Some(Vec2::new(0.0, 100.0)));
+        assert_eq!(last_right, Some(Vec2::new(200.0, 100.0)));
+    }
+
+    #[test]
+    fn test_gesture_duration_exceeds_maximum() {
+        let mut app = App::new();
+
+        let params = json!({
+            "gesture": "long_press",
+            "position": [0.0, 0.0],
+            "duration_ms": 70_000
+        });
+        let result = send_gesture_handler(In(Some(params)), app.world_mut());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_touch_updates_touches() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin))
+            .add_systems(Update, process_timed_touch_events);
+
+        let params = json!({
+            "events": [
+                { "phase": "Started", "id": 1, "position": [5.0, 5.0] },
+                { "phase": "Started", "id": 2, "position": [50.0, 50.0] }
+            ]
+        });
+        assert!(send_touch_handler(In(Some(params)), app.world_mut()).is_ok());
+        app.update();
+
+        let touches = app.world().resource::<Touches>();
+        assert!(touches.get_pressed(1).is_some());
+        assert!(touches.get_pressed(2).is_some());
+        assert!(
+            app.world_mut()
+                .query::<&TimedTouchSequence>()
+                .iter(app.world())
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_gesture_in_frames_ends_on_last_frame() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin))
+            .add_systems(Update, process_timed_touch_events);
+
+        let params = json!({
+            "gesture": "tap",
+            "position": [10.0, 10.0],
+            "duration_frames": 2
+        });
+        let response = send_gesture_handler(In(Some(params)), app.world_mut())
+            .expect("Expected success but got error");
+        assert_eq!(response["duration_frames"], 2);
+        assert!(response.get("duration_ms").is_none());
+
+        app.update();
+        app.update();
+        assert!(app.world().resource::<Touches>().get_pressed(0).is_some());
+
+        app.update();
+        assert!(app.world().resource::<Touches>().get_pressed(0).is_none());
+    }
+}
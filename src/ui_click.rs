@@ -0,0 +1,328 @@
+//! Clicking `bevy_ui` nodes over BRP
+//!
+//! A click moves the cursor of the window the node is rendered to onto the node's center, then
+//! presses a mouse button and releases it on a later frame. `ui_focus_system` sees the same
+//! input as a real click, so `Interaction` becomes `Pressed` while the button is held.
+
+use bevy::input::ButtonState;
+use bevy::input::mouse::MouseButtonInput;
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::render::camera::NormalizedRenderTarget;
+use bevy::window::{CursorMoved, PrimaryWindow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::recording::{ActiveInputRecording, send_injected};
+use crate::selector;
+
+/// Default number of frames the button is held before it is released
+const DEFAULT_HOLD_FRAMES: u32 = 1;
+
+/// Maximum number of frames the button can be held
+const MAX_HOLD_FRAMES: u32 = 3600;
+
+/// Request parameters for `brp_extras/click_ui`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClickUiRequest {
+    /// UI node entity to click
+    pub entity:      Option<Entity>,
+    /// `Name` or `Name` path of the UI node to click, as used by `brp_extras/find_entities`
+    pub name:        Option<String>,
+    /// Mouse button to click with (default: `Left`)
+    #[serde(default = "default_button")]
+    pub button:      MouseButton,
+    /// Frames to hold the button before releasing it (default: 1)
+    pub hold_frames: Option<u32>,
+}
+
+/// Response for `brp_extras/click_ui`
+#[derive(Debug, Clone, Serialize)]
+pub struct ClickUiResponse {
+    /// Whether the click was started
+    pub success:     bool,
+    /// Entity that was clicked
+    pub entity:      Entity,
+    /// Window the click was sent to
+    pub window:      Entity,
+    /// Cursor position in logical window pixels
+    pub position:    Vec2,
+    /// Mouse button that was pressed
+    pub button:      MouseButton,
+    /// Frames the button is held before it is released
+    pub hold_frames: u32,
+}
+
+/// A synthesized mouse press that is released after a number of frames
+#[derive(Component, Debug)]
+pub struct PendingClickRelease {
+    window:        Entity,
+    button:        MouseButton,
+    hold_frames:   u32,
+    frames_waited: u32,
+}
+
+const fn default_button() -> MouseButton {
+    MouseButton::Left
+}
+
+/// Handler for clicking a UI node
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: ClickUiRequest = params
+        .ok_or_else(|| invalid("Missing parameters, expected 'entity' or 'name'".to_string()))
+        .and_then(|params| {
+            serde_json::from_value(params)
+                .map_err(|e| invalid(format!("Invalid click_ui parameters: {e}")))
+        })?;
+    let hold_frames = request.hold_frames.unwrap_or(DEFAULT_HOLD_FRAMES);
+    if !(1..=MAX_HOLD_FRAMES).contains(&hold_frames) {
+        return Err(invalid(format!(
+            "hold_frames must be between 1 and {MAX_HOLD_FRAMES}"
+        )));
+    }
+
+    let entity = resolve_node(world, &request)?;
+    let (window, physical_position) = click_position(world, entity)?;
+
+    let mut window_mut = world
+        .get_mut::<Window>(window)
+        .ok_or_else(|| invalid(format!("Window {window} doesn't exist")))?;
+    window_mut.set_physical_cursor_position(Some(physical_position.as_dvec2()));
+    let position = physical_position / window_mut.scale_factor();
+
+    send_injected(
+        world,
+        CursorMoved {
+            window,
+            position,
+            delta: None,
+        },
+    );
+    send_injected(
+        world,
+        MouseButtonInput {
+            button: request.button,
+            state: ButtonState::Pressed,
+            window,
+        },
+    );
+    world.spawn(PendingClickRelease {
+        window,
+        button: request.button,
+        hold_frames,
+        frames_waited: 0,
+    });
+    debug!("Clicking UI node {entity} at {position} in window {window}");
+
+    Ok(json!(ClickUiResponse {
+        success: true,
+        entity,
+        window,
+        position,
+        button: request.button,
+        hold_frames,
+    }))
+}
+
+/// Release synthesized mouse presses once they have been held long enough
+pub fn process_click_releases(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut PendingClickRelease)>,
+    mut mouse_events: EventWriter<MouseButtonInput>,
+    mut recording: Option<ResMut<ActiveInputRecording>>,
+) {
+    for (entity, mut pending) in &mut query {
+        pending.frames_waited += 1;
+        if pending.frames_waited >= pending.hold_frames {
+            let id = mouse_events.write(MouseButtonInput {
+                button: pending.button,
+                state:  ButtonState::Released,
+                window: pending.window,
+            });
+            if let Some(recording) = recording.as_mut() {
+                recording.skip(id);
+            }
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Release every synthesized mouse press immediately
+///
+/// Returns the number of pending clicks that were released
+pub fn release_pending_clicks(world: &mut World) -> usize {
+    let pending: Vec<(Entity, Entity, MouseButton)> = world
+        .query::<(Entity, &PendingClickRelease)>()
+        .iter(world)
+        .map(|(entity, pending)| (entity, pending.window, pending.button))
+        .collect();
+
+    for &(entity, window, button) in &pending {
+        send_injected(
+            world,
+            MouseButtonInput {
+                button,
+                state: ButtonState::Released,
+                window,
+            },
+        );
+        world.despawn(entity);
+    }
+    pending.len()
+}
+
+/// Find the UI node to click from the request's entity or name
+fn resolve_node(world: &World, request: &ClickUiRequest) -> Result<Entity, BrpError> {
+    let entity = match (request.entity, request.name.as_deref()) {
+        (Some(entity), None) => entity,
+        (None, Some(name)) => {
+            let nodes: Vec<Entity> = selector::select(world, name)
+                .into_iter()
+                .filter(|&entity| world.get::<ComputedNode>(entity).is_some())
+                .collect();
+            match nodes.as_slice() {
+                [entity] => *entity,
+                [] => return Err(invalid(format!("No UI node matches '{name}'"))),
+                _ => {
+                    return Err(invalid(format!(
+                        "'{name}' matches {} UI nodes {nodes:?}, use a longer path or 'entity'",
+                        nodes.len()
+                    )));
+                }
+            }
+        }
+        _ => {
+            return Err(invalid(
+                "Specify exactly one of 'entity' or 'name'".to_string(),
+            ));
+        }
+    };
+
+    if world
+        .get::<InheritedVisibility>(entity)
+        .is_some_and(|visibility| !visibility.get())
+    {
+        return Err(invalid(format!("UI node {entity} is hidden")));
+    }
+    Ok(entity)
+}
+
+/// Find the window showing a node and the node's center in physical window pixels
+fn click_position(world: &mut World, entity: Entity) -> Result<(Entity, Vec2), BrpError> {
+    let (computed, transform, target) = world
+        .query::<(&ComputedNode, &GlobalTransform, &ComputedNodeTarget)>()
+        .get(world, entity)
+        .map_err(|_| invalid(format!("Entity {entity} is not a UI node")))?;
+    if computed.size().cmple(Vec2::ZERO).any() {
+        return Err(invalid(format!("UI node {entity} has no size")));
+    }
+    // UI layout is in physical pixels relative to the camera viewport, with the transform at the
+    // node's center
+    let center = transform.translation().truncate();
+    let camera_entity = target
+        .camera()
+        .ok_or_else(|| invalid(format!("UI node {entity} has no camera")))?;
+
+    let primary_window = world
+        .query_filtered::<Entity, With<PrimaryWindow>>()
+        .iter(world)
+        .next();
+    let camera = world
+        .get::<Camera>(camera_entity)
+        .ok_or_else(|| invalid(format!("Camera {camera_entity} doesn't exist")))?;
+    let Some(NormalizedRenderTarget::Window(window_ref)) = camera.target.normalize(primary_window)
+    else {
+        return Err(invalid(format!(
+            "UI node {entity} isn't rendered to a window, so it can't be clicked"
+        )));
+    };
+    let viewport_offset = camera
+        .physical_viewport_rect()
+        .map(|rect| rect.min.as_vec2())
+        .unwrap_or_default();
+
+    Ok((window_ref.entity(), center + viewport_offset))
+}
+
+const fn invalid(message: String) -> BrpError {
+    BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::InputPlugin;
+    use bevy::reflect::Struct;
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_click_ui_presses_and_releases() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin))
+            .add_event::<CursorMoved>()
+            .add_systems(Update, process_click_releases);
+
+        let window = app
+            .world_mut()
+            .spawn((Window::default(), PrimaryWindow))
+            .id();
+        let camera = app.world_mut().spawn(Camera::default()).id();
+        let mut target = ComputedNodeTarget::default();
+        *target
+            .field_mut("camera")
+            .and_then(|field| field.try_downcast_mut::<Entity>())
+            .expect("Expected a camera field") = camera;
+        let menu = app.world_mut().spawn(Name::new("Menu")).id();
+        let button = app
+            .world_mut()
+            .spawn((
+                Name::new("Play"),
+                ComputedNode {
+                    size: Vec2::new(100.0, 40.0),
+                    ..default()
+                },
+                GlobalTransform::from_xyz(200.0, 120.0, 0.0),
+                target,
+                ChildOf(menu),
+            ))
+            .id();
+
+        let params = json!({ "name": "Menu/Play" });
+        let response = handler(In(Some(params)), app.world_mut()).expect("Expected a click");
+        assert_eq!(response["entity"], json!(button));
+        assert_eq!(response["window"], json!(window));
+        let cursor = app
+            .world()
+            .get::<Window>(window)
+            .and_then(Window::physical_cursor_position)
+            .expect("Expected the cursor to move");
+        assert!(cursor.distance(Vec2::new(200.0, 120.0)) < 1e-3);
+
+        app.update();
+        let mouse = app.world().resource::<ButtonInput<MouseButton>>();
+        assert!(mouse.just_pressed(MouseButton::Left));
+
+        app.update();
+        let mouse = app.world().resource::<ButtonInput<MouseButton>>();
+        assert!(mouse.just_released(MouseButton::Left));
+
+        // Long presses can be released early, as `release_all` does
+        let params = json!({ "entity": button, "hold_frames": 100 });
+        assert!(handler(In(Some(params)), app.world_mut()).is_ok());
+        app.update();
+        assert_eq!(release_pending_clicks(app.world_mut()), 1);
+        app.update();
+        let mouse = app.world().resource::<ButtonInput<MouseButton>>();
+        assert!(mouse.just_released(MouseButton::Left));
+        let mut pending = app.world_mut().query::<&PendingClickRelease>();
+        assert_eq!(pending.iter(app.world()).count(), 0);
+
+        let params = json!({ "name": "Missing" });
+        assert!(handler(In(Some(params)), app.world_mut()).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! `bevy_ui` layout tree dump over BRP
+//!
+//! Reports every UI node with its computed layout, visibility, `Interaction` and text, so a
+//! client can read the screen without pixels. Sizes and positions are in logical pixels
+//! relative to the top left of the camera viewport.
+
+use bevy::prelude::*;
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Request parameters for `brp_extras/ui_tree`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UiTreeRequest {
+    /// UI node to start from instead of every UI root
+    pub entity:       Option<Entity>,
+    /// Maximum depth below the starting nodes, where 0 returns only the starting nodes
+    pub max_depth:    Option<u32>,
+    /// Leave out hidden nodes and their descendants
+    #[serde(default)]
+    pub visible_only: bool,
+}
+
+/// How much of a node is cut off by its ancestors' `Overflow` clipping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Clipping {
+    /// The node is entirely inside its clip rect, or isn't clipped
+    None,
+    /// Part of the node is outside its clip rect
+    Partial,
+    /// The node is entirely outside its clip rect
+    Full,
+}
+
+/// A UI node in the tree dump
+#[derive(Debug, Clone, Serialize)]
+pub struct UiNodeInfo {
+    /// Entity ID
+    pub entity:      Entity,
+    /// The node's `Name`, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name:        Option<String>,
+    /// Top left corner in logical pixels
+    pub position:    Vec2,
+    /// Computed size in logical pixels
+    pub size:        Vec2,
+    /// Whether the node is visible according to `InheritedVisibility`
+    pub visible:     bool,
+    /// The node's `Interaction`, for buttons and other interactive nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interaction: Option<Interaction>,
+    /// Text content from `Text` and its `TextSpan` descendants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text:        Option<String>,
+    /// Clip rect from the node's ancestors in logical pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clip:        Option<Rect>,
+    /// How much of the node its clip rect cuts off
+    pub clipping:    Clipping,
+    /// Child UI nodes within the depth limit
+    pub children:    Vec<Self>,
+}
+
+/// Handler for dumping the UI tree
+#[allow(clippy::needless_pass_by_ref_mut)]
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let request: UiTreeRequest = match params {
+        Some(params) => serde_json::from_value(params).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid ui_tree parameters: {e}"),
+            data:    None,
+        })?,
+        None => UiTreeRequest::default(),
+    };
+
+    let nodes = build_ui_tree(world, &request)?;
+    let node_count = nodes.iter().map(count_nodes).sum::<usize>();
+
+    Ok(json!({
+        "success": true,
+        "roots": nodes,
+        "node_count": node_count
+    }))
+}
+
+/// Build the tree for the requested starting nodes
+fn build_ui_tree(world: &World, request: &UiTreeRequest) -> Result<Vec<UiNodeInfo>, BrpError> {
+    let starts = if let Some(entity) = request.entity {
+        if world.get::<ComputedNode>(entity).is_none() {
+            return Err(BrpError {
+                code:    error_codes::INVALID_PARAMS,
+                message: format!("Entity {entity} is not a UI node"),
+                data:    None,
+            });
+        }
+        vec![entity]
+    } else {
+        // Roots are UI nodes whose parent isn't a UI node, in stacking order
+        let mut roots: Vec<(u32, Entity)> = world
+            .iter_entities()
+            .filter_map(|entity| {
+                let computed = entity.get::<ComputedNode>()?;
+                let parent_is_node = entity
+                    .get::<ChildOf>()
+                    .is_some_and(|child_of| world.get::<ComputedNode>(child_of.parent()).is_some());
+                (!parent_is_node).then_some((computed.stack_index(), entity.id()))
+            })
+            .collect();
+        roots.sort_unstable();
+        roots.into_iter().map(|(_, entity)| entity).collect()
+    };
+
+    Ok(starts
+        .into_iter()
+        .filter_map(|entity| build_node(world, request, entity, 0))
+        .collect())
+}
+
+fn build_node(
+    world: &World,
+    request: &UiTreeRequest,
+    entity: Entity,
+    depth: u32,
+) -> Option<UiNodeInfo> {
+    let entity_ref = world.get_entity(entity).ok()?;
+    let computed = entity_ref.get::<ComputedNode>()?;
+    let visible = entity_ref
+        .get::<InheritedVisibility>()
+        .is_none_or(|visibility| visibility.get());
+    if request.visible_only && !visible {
+        return None;
+    }
+
+    // Layout is in physical pixels, with the transform at the node's center
+    let scale = computed.inverse_scale_factor();
+    let center = entity_ref
+        .get::<GlobalTransform>()
+        .map(|transform| transform.translation().truncate())
+        .unwrap_or_default();
+    let rect = Rect::from_center_size(center, computed.size());
+    let clip = entity_ref.get::<CalculatedClip>().map(|clip| clip.clip);
+    let clipping = clip.map_or(Clipping::None, |clip| {
+        let visible_rect = rect.intersect(clip);
+        if visible_rect.is_empty() {
+            Clipping::Full
+        } else if visible_rect == rect {
+            Clipping::None
+        } else {
+            Clipping::Partial
+        }
+    });
+
+    let children = if request.max_depth.is_some_and(|max| depth >= max) {
+        Vec::new()
+    } else {
+        entity_ref
+            .get::<Children>()
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|child| build_node(world, request, child, depth + 1))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Some(UiNodeInfo {
+        entity,
+        name: entity_ref
+            .get::<Name>()
+            .map(|name| name.as_str().to_string()),
+        position: rect.min * scale,
+        size: rect.size() * scale,
+        visible,
+        interaction: entity_ref.get::<Interaction>().copied(),
+        text: entity_ref.get::<Text>().map(|text| {
+            let mut content = text.0.clone();
+            append_spans(world, entity, &mut content);
+            content
+        }),
+        clip: clip.map(|clip| Rect {
+            min: clip.min * scale,
+            max: clip.max * scale,
+        }),
+        clipping,
+        children,
+    })
+}
+
+/// Append the text of an entity's `TextSpan` descendants in order
+fn append_spans(world: &World, entity: Entity, content: &mut String) {
+    let Some(children) = world.get::<Children>(entity) else {
+        return;
+    };
+    for child in children.iter() {
+        if let Some(span) = world.get::<TextSpan>(child) {
+            content.push_str(&span.0);
+            append_spans(world, child, content);
+        }
+    }
+}
+
+fn count_nodes(node: &UiNodeInfo) -> usize {
+    1 + node.children.iter().map(count_nodes).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_ui_tree() {
+        let mut world = World::new();
+        let panel = world
+            .spawn((
+                Name::new("Panel"),
+                ComputedNode {
+                    size: Vec2::new(400.0, 200.0),
+                    inverse_scale_factor: 0.5,
+                    ..default()
+                },
+                GlobalTransform::from_xyz(200.0, 100.0, 0.0),
+            ))
+            .id();
+        world.spawn((
+            Name::new("Play"),
+            Interaction::Hovered,
+            ComputedNode {
+                size: Vec2::new(100.0, 40.0),
+                inverse_scale_factor: 0.5,
+                ..default()
+            },
+            GlobalTransform::from_xyz(380.0, 100.0, 0.0),
+            CalculatedClip {
+                clip: Rect::new(0.0, 0.0, 400.0, 200.0),
+            },
+            ChildOf(panel),
+        ));
+        let label = world
+            .spawn((
+                Text::new("Score: "),
+                InheritedVisibility::HIDDEN,
+                ChildOf(panel),
+            ))
+            .id();
+        world.spawn((TextSpan::new("42"), ChildOf(label)));
+
+        let nodes = build_ui_tree(&world, &UiTreeRequest::default()).expect("Expected a tree");
+        assert_eq!(nodes.len(), 1);
+        let panel_info = &nodes[0];
+        assert_eq!(panel_info.size, Vec2::new(200.0, 100.0));
+        assert_eq!(panel_info.position, Vec2::ZERO);
+        assert_eq!(panel_info.children.len(), 2);
+
+        let button = &panel_info.children[0];
+        assert_eq!(button.interaction, Some(Interaction::Hovered));
+        assert_eq!(button.position, Vec2::new(165.0, 40.0));
+        assert_eq!(button.clipping, Clipping::Partial);
+
+        let text = &panel_info.children[1];
+        assert_eq!(text.text.as_deref(), Some("Score: 42"));
+        assert!(!text.visible);
+
+        let request = UiTreeRequest {
+            visible_only: true,
+            ..default()
+        };
+        let nodes = build_ui_tree(&world, &request).expect("Expected a tree");
+        assert_eq!(nodes[0].children.len(), 1);
+    }
+}
@@ -0,0 +1,487 @@
+//! Waiting for a condition to hold before responding
+//!
+//! `brp_extras/wait_for` responds once its condition holds or its timeout passes, which lets test
+//! scripts synchronize with the app instead of sleeping. The condition is checked once per frame.
+
+use std::time::Instant;
+
+use bevy::ecs::component::Tick;
+use bevy::prelude::*;
+use bevy::reflect::{ReflectPath, TypeRegistry};
+use bevy::remote::{BrpError, BrpResult, error_codes};
+use bevy::state::reflect::ReflectState;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::deferred::{DeferredStep, run_deferred, wait_deadline};
+use crate::reflection::{json_matches, registration, to_json};
+
+/// Request parameters for `brp_extras/wait_for`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaitForRequest {
+    /// Condition to wait for
+    #[serde(flatten)]
+    pub condition:  WaitCondition,
+    /// Maximum time to wait in milliseconds (default: 5000, maximum: 60000)
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+}
+
+/// Condition that `brp_extras/wait_for` waits for
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitCondition {
+    /// The given number of frames have passed
+    Frames(u32),
+    /// At least one entity matches the filter
+    EntityExists(EntityFilter),
+    /// No entity matches the filter
+    EntityRemoved(EntityFilter),
+    /// A component, or a field of it, equals a value on a matching entity
+    ComponentValue(ComponentValueCondition),
+    /// A resource, given by type path, is inserted or changed
+    ResourceChanged(String),
+    /// A `States` type has the given value
+    StateEntered(StateCondition),
+}
+
+/// Entities selected by component and `Name`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EntityFilter {
+    /// Type path of a component the entity must have
+    #[serde(default)]
+    pub component: Option<String>,
+    /// `Name` the entity must have
+    #[serde(default)]
+    pub name:      Option<String>,
+}
+
+/// Component value to wait for
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComponentValueCondition {
+    /// Entity to check, instead of every entity with the component
+    #[serde(default)]
+    pub entity:    Option<Entity>,
+    /// `Name` of the entities to check
+    #[serde(default)]
+    pub name:      Option<String>,
+    /// Type path of the component
+    pub component: String,
+    /// Reflection path to a field of the component, such as `translation.x`
+    #[serde(default)]
+    pub field:     Option<String>,
+    /// Value in the JSON format used by BRP
+    pub value:     Value,
+}
+
+/// State value to wait for
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateCondition {
+    /// Type path of the `States` type
+    pub state: String,
+    /// Value in the JSON format used by BRP, such as `"InGame"` for a unit variant
+    pub value: Value,
+}
+
+/// Progress of a wait that has not finished yet
+pub struct PendingWait {
+    request:  WaitForRequest,
+    started:  Instant,
+    deadline: Instant,
+    since:    Tick,
+    frames:   u32,
+}
+
+/// Handler for `brp_extras/wait_for`
+///
+/// Registered as a watching method so it responds once the condition holds.
+pub fn handler(In(params): In<Option<Value>>, world: &mut World) -> BrpResult<Option<Value>> {
+    run_deferred(world, params, start_wait, poll_wait)
+}
+
+fn start_wait(world: &mut World, params: Option<Value>) -> DeferredStep<PendingWait> {
+    match new_wait(world, params) {
+        Ok(wait) => check_wait(world, wait),
+        Err(e) => DeferredStep::Ready(Err(e)),
+    }
+}
+
+fn new_wait(world: &World, params: Option<Value>) -> Result<PendingWait, BrpError> {
+    let request: WaitForRequest =
+        serde_json::from_value(params.unwrap_or(Value::Null)).map_err(|e| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("Invalid wait_for parameters: {e}"),
+            data:    None,
+        })?;
+    let deadline = wait_deadline(request.timeout_ms)?;
+    Ok(PendingWait {
+        request,
+        started: Instant::now(),
+        deadline,
+        since: world.read_change_tick(),
+        frames: 0,
+    })
+}
+
+fn poll_wait(world: &mut World, mut wait: PendingWait) -> DeferredStep<PendingWait> {
+    wait.frames = wait.frames.saturating_add(1);
+    check_wait(world, wait)
+}
+
+fn check_wait(world: &World, wait: PendingWait) -> DeferredStep<PendingWait> {
+    match evaluate(world, &wait.request.condition, &wait) {
+        Ok(Some(details)) => {
+            let mut response = json!({
+                "success": true,
+                "frames_waited": wait.frames,
+                "elapsed_ms": wait.started.elapsed().as_millis(),
+            });
+            if let (Value::Object(response), Value::Object(details)) = (&mut response, details) {
+                response.extend(details);
+            }
+            DeferredStep::Ready(Ok(response))
+        }
+        Ok(None) if Instant::now() >= wait.deadline => DeferredStep::Ready(Err(BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: format!(
+                "Timed out after {} frames waiting for {}",
+                wait.frames,
+                describe(&wait.request.condition)
+            ),
+            data:    Some(json!({ "frames_waited": wait.frames })),
+        })),
+        Ok(None) => DeferredStep::Pending(wait),
+        Err(e) => DeferredStep::Ready(Err(e)),
+    }
+}
+
+/// Check a condition, returning details for the response once it holds
+fn evaluate(
+    world: &World,
+    condition: &WaitCondition,
+    wait: &PendingWait,
+) -> Result<Option<Value>, BrpError> {
+    if let WaitCondition::Frames(frames) = condition {
+        return Ok((wait.frames >= *frames).then(|| json!({})));
+    }
+
+    let type_registry = world
+        .get_resource::<AppTypeRegistry>()
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INTERNAL_ERROR,
+            message: "AppTypeRegistry is not available".to_string(),
+            data:    None,
+        })?
+        .clone();
+    let registry = type_registry.read();
+    evaluate_reflected(world, &registry, condition, wait)
+}
+
+/// Check a condition that needs the type registry
+fn evaluate_reflected(
+    world: &World,
+    registry: &TypeRegistry,
+    condition: &WaitCondition,
+    wait: &PendingWait,
+) -> Result<Option<Value>, BrpError> {
+    match condition {
+        WaitCondition::Frames(_) => Ok(None),
+        WaitCondition::EntityExists(filter) => {
+            let entities = matching_entities(world, registry, filter)?;
+            Ok((!entities.is_empty()).then(|| json!({ "entities": entities })))
+        }
+        WaitCondition::EntityRemoved(filter) => {
+            let entities = matching_entities(world, registry, filter)?;
+            Ok(entities.is_empty().then(|| json!({})))
+        }
+        WaitCondition::ComponentValue(condition) => {
+            component_value_matches(world, registry, condition)
+        }
+        WaitCondition::ResourceChanged(resource) => {
+            let type_id = registration(registry, resource)?.type_id();
+            let changed = world
+                .components()
+                .get_resource_id(type_id)
+                .and_then(|id| world.get_resource_change_ticks_by_id(id))
+                .is_some_and(|ticks| {
+                    ticks
+                        .changed
+                        .is_newer_than(wait.since, world.read_change_tick())
+                });
+            Ok(changed.then(|| json!({})))
+        }
+        WaitCondition::StateEntered(condition) => {
+            let reflect_state = registration(registry, &condition.state)?
+                .data::<ReflectState>()
+                .ok_or_else(|| BrpError {
+                    code:    error_codes::INVALID_PARAMS,
+                    message: format!(
+                        "'{}' is not a reflected state, register it with \
+                         App::register_type_state",
+                        condition.state
+                    ),
+                    data:    None,
+                })?;
+            let Some(state) = reflect_state.reflect(world) else {
+                return Ok(None);
+            };
+            let state = to_json(state.as_partial_reflect(), registry)?;
+            Ok(json_matches(&state, &condition.value).then(|| json!({ "state": state })))
+        }
+    }
+}
+
+/// Entities matching a filter of component and `Name`
+fn matching_entities(
+    world: &World,
+    registry: &TypeRegistry,
+    filter: &EntityFilter,
+) -> Result<Vec<Entity>, BrpError> {
+    if filter.component.is_none() && filter.name.is_none() {
+        return Err(BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: "Entity conditions need a 'component', a 'name' or both".to_string(),
+            data:    None,
+        });
+    }
+    let component = match filter.component.as_deref() {
+        Some(component) => {
+            let type_id = registration(registry, component)?.type_id();
+            // A component that was never added to an entity has no ID yet
+            let Some(id) = world.components().get_id(type_id) else {
+                return Ok(Vec::new());
+            };
+            Some(id)
+        }
+        None => None,
+    };
+
+    Ok(world
+        .iter_entities()
+        .filter(|entity| component.is_none_or(|id| entity.contains_id(id)))
+        .filter(|entity| {
+            filter
+                .name
+                .as_deref()
+                .is_none_or(|name| entity.get::<Name>().is_some_and(|n| n.as_str() == name))
+        })
+        .map(|entity| entity.id())
+        .collect())
+}
+
+/// Check a component value condition, returning the matching entity and value
+fn component_value_matches(
+    world: &World,
+    registry: &TypeRegistry,
+    condition: &ComponentValueCondition,
+) -> Result<Option<Value>, BrpError> {
+    let reflect_component = registration(registry, &condition.component)?
+        .data::<ReflectComponent>()
+        .ok_or_else(|| BrpError {
+            code:    error_codes::INVALID_PARAMS,
+            message: format!("'{}' is not a reflected component", condition.component),
+            data:    None,
+        })?;
+
+    let entities = match condition.entity {
+        Some(entity) => vec![entity],
+        None => matching_entities(
+            world,
+            registry,
+            &EntityFilter {
+                component: Some(condition.component.clone()),
+                name:      condition.name.clone(),
+            },
+        )?,
+    };
+
+    for entity in entities {
+        let Some(component) = world
+            .get_entity(entity)
+            .ok()
+            .and_then(|entity| reflect_component.reflect(entity))
+        else {
+            continue;
+        };
+        let value = match condition.field.as_deref() {
+            Some(field) => field
+                .reflect_element(component.as_partial_reflect())
+                .map_err(|e| BrpError {
+                    code:    error_codes::INVALID_PARAMS,
+                    message: format!("Invalid field '{field}' of {}: {e}", condition.component),
+                    data:    None,
+                })?,
+            None => component.as_partial_reflect(),
+        };
+        let value = to_json(value, registry)?;
+        if json_matches(&value, &condition.value) {
+            return Ok(Some(json!({ "entity": entity, "value": value })));
+        }
+    }
+    Ok(None)
+}
+
+/// Short description of a condition for timeout errors
+fn describe(condition: &WaitCondition) -> String {
+    match condition {
+        WaitCondition::Frames(frames) => format!("{frames} frames"),
+        WaitCondition::EntityExists(_) => "a matching entity to exist".to_string(),
+        WaitCondition::EntityRemoved(_) => "matching entities to be removed".to_string(),
+        WaitCondition::ComponentValue(condition) => {
+            format!("{} to equal {}", condition.component, condition.value)
+        }
+        WaitCondition::ResourceChanged(resource) => format!("{resource} to change"),
+        WaitCondition::StateEntered(condition) => {
+            format!("{} to be {}", condition.state, condition.value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::deferred::with_request_id;
+
+    #[derive(States, Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    enum TestState {
+        #[default]
+        Loading,
+        InGame,
+    }
+
+    #[derive(Resource, Reflect, Default)]
+    #[reflect(Resource)]
+    struct Score(u32);
+
+    fn wait_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        {
+            let mut registry = world.resource::<AppTypeRegistry>().write();
+            registry.register::<Name>();
+            registry.register::<Transform>();
+            registry.register::<Score>();
+        }
+        world
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_wait_for_frames() {
+        let mut world = wait_world();
+        let request = |id| with_request_id(json!({ "frames": 2 }), id);
+
+        // Identical waits in flight at the same time each count their own frames
+        for _ in 0..2 {
+            assert!(matches!(handler(In(request(1)), &mut world), Ok(None)));
+            assert!(matches!(handler(In(request(2)), &mut world), Ok(None)));
+        }
+        for id in [1, 2] {
+            let response = handler(In(request(id)), &mut world)
+                .expect("Expected success")
+                .expect("Expected response");
+            assert_eq!(response["frames_waited"], 2);
+        }
+
+        // The extra run after responding repeats the result, a new request waits again
+        let response = handler(In(request(1)), &mut world)
+            .expect("Expected success")
+            .expect("Expected response");
+        assert_eq!(response["frames_waited"], 2);
+        assert!(matches!(handler(In(request(3)), &mut world), Ok(None)));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_wait_for_entities_and_values() {
+        let mut world = wait_world();
+        let params = with_request_id(json!({ "entity_exists": { "name": "Player" } }), 1);
+        assert!(matches!(handler(In(params.clone()), &mut world), Ok(None)));
+
+        let player = world
+            .spawn((Name::new("Player"), Transform::from_xyz(1.0, 2.0, 3.0)))
+            .id();
+        let response = handler(In(params), &mut world)
+            .expect("Expected success")
+            .expect("Expected response");
+        assert_eq!(response["entities"], json!([player]));
+
+        let params = with_request_id(
+            json!({
+                "component_value": {
+                    "name": "Player",
+                    "component": "bevy_transform::components::transform::Transform",
+                    "field": "translation.x",
+                    "value": 1.0
+                }
+            }),
+            2,
+        );
+        let response = handler(In(params), &mut world)
+            .expect("Expected success")
+            .expect("Expected response");
+        assert_eq!(response["entity"], json!(player));
+
+        let params = with_request_id(json!({ "entity_removed": { "name": "Player" } }), 3);
+        assert!(matches!(handler(In(params.clone()), &mut world), Ok(None)));
+        world.despawn(player);
+        assert!(matches!(handler(In(params), &mut world), Ok(Some(_))));
+
+        let params = with_request_id(
+            json!({ "entity_exists": { "component": "missing::Type" } }),
+            4,
+        );
+        assert!(handler(In(params), &mut world).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_wait_for_resource_change_and_timeout() {
+        let mut world = wait_world();
+        world.init_resource::<Score>();
+        let path = std::any::type_name::<Score>();
+        let params = with_request_id(json!({ "resource_changed": path }), 5);
+
+        assert!(matches!(handler(In(params.clone()), &mut world), Ok(None)));
+        world.increment_change_tick();
+        world.resource_mut::<Score>().0 += 1;
+        assert!(matches!(handler(In(params), &mut world), Ok(Some(_))));
+
+        let params = with_request_id(json!({ "frames": 100, "timeout_ms": 0 }), 6);
+        let error = handler(In(params), &mut world).expect_err("Expected timeout");
+        assert!(error.message.contains("Timed out"));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_wait_for_state() {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_state::<TestState>()
+            .register_type_state::<TestState>();
+        app.update();
+        let world = app.world_mut();
+        let params = with_request_id(
+            json!({
+                "state_entered": {
+                    "state": std::any::type_name::<TestState>(),
+                    "value": "InGame"
+                }
+            }),
+            7,
+        );
+
+        assert!(matches!(handler(In(params.clone()), world), Ok(None)));
+        world
+            .resource_mut::<NextState<TestState>>()
+            .set(TestState::InGame);
+        app.update();
+        let world = app.world_mut();
+        let response = handler(In(params), world)
+            .expect("Expected success")
+            .expect("Expected response");
+        assert_eq!(response["state"], "InGame");
+    }
+}